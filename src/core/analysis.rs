@@ -0,0 +1,188 @@
+use std::collections::BTreeMap;
+
+use crate::core::decoder::decode_bytes;
+use crate::core::instruction::{Immediate, Instruction, Mnemonic, Operand, Register};
+
+// Where execution can go after an instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    // On to the next instruction
+    Next,
+    // Unconditional jump, None when the target is indirect or far
+    Jump(Option<u16>),
+    // Conditional jump or loop: the target or the next instruction
+    Branch(u16),
+    // Subroutine call, returning to the next instruction
+    Call(Option<u16>),
+    // RET, IRET or a program exit
+    Stop,
+}
+
+pub fn control_flow(ins: &Instruction) -> Flow {
+    use Mnemonic::*;
+    let target = match ins.operands[0] {
+        Some(Operand::RelTarget { target, .. }) => Some(target),
+        _ => None,
+    };
+    match ins.mnemonic {
+        Jmp => Flow::Jump(target),
+        Call => Flow::Call(target),
+        Jo | Jno | Jb | Jae | Je | Jne | Jbe | Ja | Js | Jns | Jp | Jnp | Jl | Jge | Jle | Jg
+        | Jcxz | Loopnz | Loopz | Loop => Flow::Branch(target.expect("relative target")),
+        Ret | Retf | Iret => Flow::Stop,
+        // INT 20h ends a .COM program, INT 27h ends it and stays resident
+        Int if matches!(
+            ins.operands[0],
+            Some(Operand::Imm(Immediate::Byte(0x20 | 0x27)))
+        ) =>
+        {
+            Flow::Stop
+        }
+        _ => Flow::Next,
+    }
+}
+
+// control_flow, with INT 21h/4Ch recognised as an exit when the instruction
+// before it loads AH
+pub fn control_flow_after(previous: Option<&Instruction>, ins: &Instruction) -> Flow {
+    match control_flow(ins) {
+        Flow::Next if previous.is_some_and(|prev| is_dos_exit(prev, ins)) => Flow::Stop,
+        flow => flow,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Label {
+    // Target of a call
+    Sub,
+    // Target of a jump or branch
+    Loc,
+}
+
+// Result of following control flow through a program image. Whatever isn't
+// covered by an instruction is data.
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    pub origin: u16,
+    // Reached instructions by address
+    pub code: BTreeMap<u16, Instruction>,
+    pub labels: BTreeMap<u16, Label>,
+}
+
+impl Analysis {
+    pub fn label(&self, address: u16) -> Option<String> {
+        self.labels.get(&address).map(|label| match label {
+            Label::Sub => format!("sub_{:04X}", address),
+            Label::Loc => format!("loc_{:04X}", address),
+        })
+    }
+}
+
+// Recursive descent from `entry`: decodes along every path, queueing branch
+// and call targets as it goes, until each path ends in a return, an exit, an
+// indirect jump, undecodable bytes or code it has already seen.
+pub fn analyze(image: &[u8], origin: u16, entry: u16) -> Analysis {
+    let mut claimed = vec![false; image.len()];
+    let mut code = BTreeMap::new();
+    let mut labels = BTreeMap::new();
+    let mut pending = vec![entry];
+    let offset_of = |address: u16| {
+        let offset = address.wrapping_sub(origin) as usize;
+        (offset < image.len()).then_some(offset)
+    };
+
+    while let Some(start) = pending.pop() {
+        let mut address = start;
+        let mut previous: Option<Instruction> = None;
+        while let Some(offset) = offset_of(address) {
+            if claimed[offset] {
+                break;
+            }
+            let Ok(ins) = decode_bytes(&image[offset..], address) else {
+                break;
+            };
+            let end = offset + ins.length as usize;
+            // Bytes already taken by another instruction, the paths disagree
+            if claimed[offset..end].iter().any(|&taken| taken) {
+                break;
+            }
+            claimed[offset..end].fill(true);
+            code.insert(address, ins);
+
+            let flow = control_flow_after(previous.as_ref(), &ins);
+            match flow {
+                Flow::Jump(Some(target)) | Flow::Branch(target) if offset_of(target).is_some() => {
+                    labels.entry(target).or_insert(Label::Loc);
+                    pending.push(target);
+                }
+                Flow::Call(Some(target)) if offset_of(target).is_some() => {
+                    labels.insert(target, Label::Sub);
+                    pending.push(target);
+                }
+                _ => {}
+            }
+            if matches!(flow, Flow::Jump(_) | Flow::Stop) {
+                break;
+            }
+            previous = Some(ins);
+            address = address.wrapping_add(ins.length as u16);
+        }
+    }
+
+    // A target inside another instruction gets no label, there is no line
+    // to put it on
+    labels.retain(|address, _| code.contains_key(address));
+
+    Analysis {
+        origin,
+        code,
+        labels,
+    }
+}
+
+// MOV AH, 4Ch or MOV AX, 4Cxxh followed by INT 21h terminates the program
+fn is_dos_exit(previous: &Instruction, ins: &Instruction) -> bool {
+    let exit_call = match (previous.mnemonic, previous.operands) {
+        (Mnemonic::Mov, [Some(Operand::Reg(Register::AH)), Some(Operand::Imm(imm))]) => {
+            imm == Immediate::Byte(0x4C)
+        }
+        (Mnemonic::Mov, [Some(Operand::Reg(Register::AX)), Some(Operand::Imm(imm))]) => {
+            matches!(imm, Immediate::Word(ax) if ax >> 8 == 0x4C)
+        }
+        _ => false,
+    };
+    exit_call
+        && ins.mnemonic == Mnemonic::Int
+        && ins.operands[0] == Some(Operand::Imm(Immediate::Byte(0x21)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_only_instruction_starts() {
+        // call 0x10b; 7 nops; mov word [bp+si+0x0], 0x1234; ret, where the
+        // call lands on the second byte of the mov
+        let mut image = vec![0xE8, 0x08, 0x00];
+        image.extend([0x90; 7]);
+        image.extend([0xC7, 0x42, 0x00, 0x34, 0x12, 0xC3]);
+        let analysis = analyze(&image, 0x100, 0x100);
+
+        assert!(analysis.code.contains_key(&0x10A));
+        assert!(!analysis.code.contains_key(&0x10B));
+        assert_eq!(analysis.label(0x10B), None);
+        assert!(analysis.labels.is_empty());
+    }
+
+    #[test]
+    fn labels_calls_and_jumps() {
+        // call 0x106; jmp 0x105; ret; ret
+        let image = [0xE8, 0x03, 0x00, 0xEB, 0x00, 0xC3, 0xC3];
+        let analysis = analyze(&image, 0x100, 0x100);
+
+        assert_eq!(analysis.label(0x106).as_deref(), Some("sub_0106"));
+        assert_eq!(analysis.label(0x105).as_deref(), Some("loc_0105"));
+        assert_eq!(analysis.labels.len(), 2);
+    }
+}
@@ -0,0 +1,305 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::core::analysis::{Flow, control_flow_after};
+use crate::core::decoder::decode_bytes;
+use crate::core::format::Syntax;
+use crate::core::instruction::Instruction;
+use crate::core::symbols::SymbolTable;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    Taken,
+    FallThrough,
+    Call,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    pub from: u16,
+    pub to: u16,
+    pub kind: EdgeKind,
+}
+
+// Straight-line run of instructions with a single entry at the top. Calls
+// don't end a block, they only add an edge to the callee.
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub start: u16,
+    pub instructions: Vec<(u16, Instruction)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Cfg {
+    pub entry: u16,
+    // Blocks by start address
+    pub blocks: BTreeMap<u16, Block>,
+    pub edges: Vec<Edge>,
+}
+
+// Builds the control-flow graph of the function at `entry`, following
+// branches and jumps but not descending into calls
+pub fn build(image: &[u8], origin: u16, entry: u16) -> Cfg {
+    let offset_of = |address: u16| {
+        let offset = address.wrapping_sub(origin) as usize;
+        (offset < image.len()).then_some(offset)
+    };
+
+    // Everything reachable inside the function, and where blocks have to start
+    let mut code = BTreeMap::new();
+    let mut leaders = BTreeSet::from([entry]);
+    let mut pending = vec![(entry, None)];
+    while let Some((address, previous)) = pending.pop() {
+        if code.contains_key(&address) {
+            continue;
+        }
+        let Some(offset) = offset_of(address) else {
+            continue;
+        };
+        let Ok(ins) = decode_bytes(&image[offset..], address) else {
+            continue;
+        };
+        code.insert(address, ins);
+
+        let next = address.wrapping_add(ins.length as u16);
+        match control_flow_after(previous.as_ref(), &ins) {
+            Flow::Next | Flow::Call(_) => pending.push((next, Some(ins))),
+            Flow::Branch(target) => {
+                leaders.extend([target, next]);
+                pending.extend([(target, None), (next, None)]);
+            }
+            Flow::Jump(Some(target)) => {
+                leaders.insert(target);
+                pending.push((target, None));
+            }
+            Flow::Jump(None) | Flow::Stop => {}
+        }
+    }
+
+    let mut blocks = BTreeMap::new();
+    let mut edges = Vec::new();
+    for &start in leaders.iter().filter(|start| code.contains_key(start)) {
+        let mut instructions = Vec::new();
+        let mut address = start;
+        let mut previous = None;
+        loop {
+            let ins = code[&address];
+            instructions.push((address, ins));
+            let next = address.wrapping_add(ins.length as u16);
+            let flow = control_flow_after(previous.as_ref(), &ins);
+            let edge = |to, kind| Edge {
+                from: start,
+                to,
+                kind,
+            };
+            match flow {
+                Flow::Call(Some(target)) => edges.push(edge(target, EdgeKind::Call)),
+                Flow::Branch(target) => {
+                    edges.push(edge(target, EdgeKind::Taken));
+                    edges.push(edge(next, EdgeKind::FallThrough));
+                }
+                Flow::Jump(Some(target)) => edges.push(edge(target, EdgeKind::Taken)),
+                _ => {}
+            }
+            if matches!(flow, Flow::Branch(_) | Flow::Jump(_) | Flow::Stop)
+                || !code.contains_key(&next)
+            {
+                break;
+            }
+            if leaders.contains(&next) {
+                edges.push(edge(next, EdgeKind::FallThrough));
+                break;
+            }
+            previous = Some(ins);
+            address = next;
+        }
+        blocks.insert(
+            start,
+            Block {
+                start,
+                instructions,
+            },
+        );
+    }
+
+    Cfg {
+        entry,
+        blocks,
+        edges,
+    }
+}
+
+impl Cfg {
+    // sub_ for the function and its callees, loc_ for the other blocks
+    pub fn label(&self, address: u16) -> Option<String> {
+        let callee = self
+            .edges
+            .iter()
+            .any(|edge| edge.kind == EdgeKind::Call && edge.to == address);
+        if address == self.entry || callee {
+            Some(format!("sub_{:04X}", address))
+        } else if self.blocks.contains_key(&address) || self.edges.iter().any(|e| e.to == address) {
+            Some(format!("loc_{:04X}", address))
+        } else {
+            None
+        }
+    }
+
+    // Graphviz digraph with one box per block holding its disassembly. Call
+    // targets and branches leaving the image show up as plain ellipses.
+    // Known symbols replace the generated labels in the text.
+    pub fn to_dot(&self, syntax: Syntax, symbols: &SymbolTable) -> String {
+        let labels = |address| {
+            let symbol = symbols.name(address).map(str::to_string);
+            symbol.or_else(|| self.label(address))
+        };
+        let name = |address| self.label(address).unwrap();
+        let mut dot = format!("digraph {} {{\n", name(self.entry));
+        dot.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+
+        for block in self.blocks.values() {
+            let mut text = format!("{}:\\l", escape(&labels(block.start).unwrap()));
+            for (address, ins) in &block.instructions {
+                let line = format!(
+                    "{:04X}  {}",
+                    address,
+                    ins.display(syntax).with_symbols(&labels)
+                );
+                text.push_str(&escape(&line));
+                text.push_str("\\l");
+            }
+            dot.push_str(&format!(
+                "    {} [label=\"{}\"];\n",
+                name(block.start),
+                text
+            ));
+        }
+
+        let outside: BTreeSet<u16> = self
+            .edges
+            .iter()
+            .map(|edge| edge.to)
+            .filter(|to| !self.blocks.contains_key(to))
+            .collect();
+        for address in outside {
+            let text = escape(&labels(address).unwrap());
+            dot.push_str(&format!(
+                "    {} [shape=ellipse, label=\"{}\"];\n",
+                name(address),
+                text
+            ));
+        }
+
+        for edge in &self.edges {
+            let style = match edge.kind {
+                EdgeKind::Taken => "label=\"taken\", color=darkgreen",
+                EdgeKind::FallThrough => "label=\"fall-through\", color=red",
+                EdgeKind::Call => "label=\"call\", style=dashed",
+            };
+            dot.push_str(&format!(
+                "    {} -> {} [{}];\n",
+                name(edge.from),
+                name(edge.to),
+                style
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+// Inside a quoted DOT label
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(from: u16, to: u16, kind: EdgeKind) -> Edge {
+        Edge { from, to, kind }
+    }
+
+    fn starts(cfg: &Cfg) -> Vec<(u16, usize)> {
+        cfg.blocks
+            .values()
+            .map(|block| (block.start, block.instructions.len()))
+            .collect()
+    }
+
+    #[test]
+    fn splits_a_conditional_diamond() {
+        // je 0x105; inc ax; jmp 0x106; dec ax; ret
+        let image = [0x74, 0x03, 0x40, 0xEB, 0x01, 0x48, 0xC3];
+        let cfg = build(&image, 0x100, 0x100);
+
+        assert_eq!(
+            starts(&cfg),
+            [(0x100, 1), (0x102, 2), (0x105, 1), (0x106, 1)]
+        );
+        assert_eq!(
+            cfg.edges,
+            [
+                edge(0x100, 0x105, EdgeKind::Taken),
+                edge(0x100, 0x102, EdgeKind::FallThrough),
+                edge(0x102, 0x106, EdgeKind::Taken),
+                edge(0x105, 0x106, EdgeKind::FallThrough),
+            ]
+        );
+    }
+
+    #[test]
+    fn loops_back_to_the_top_of_a_block() {
+        // mov cx, 5; inc ax; loop 0x103; ret
+        let image = [0xB9, 0x05, 0x00, 0x40, 0xE2, 0xFD, 0xC3];
+        let cfg = build(&image, 0x100, 0x100);
+
+        assert_eq!(starts(&cfg), [(0x100, 1), (0x103, 2), (0x106, 1)]);
+        assert_eq!(
+            cfg.edges,
+            [
+                edge(0x100, 0x103, EdgeKind::FallThrough),
+                edge(0x103, 0x103, EdgeKind::Taken),
+                edge(0x103, 0x106, EdgeKind::FallThrough),
+            ]
+        );
+        let dot = cfg.to_dot(Syntax::Intel, &SymbolTable::new());
+        assert!(dot.contains("    loc_0103 -> loc_0103 [label=\"taken\", color=darkgreen];\n"));
+    }
+
+    #[test]
+    fn calls_point_at_a_sub_node() {
+        // call 0x105; ret; nop; ret
+        let image = [0xE8, 0x02, 0x00, 0xC3, 0x90, 0xC3];
+        let cfg = build(&image, 0x100, 0x100);
+
+        // The call doesn't end the block and the callee isn't part of it
+        assert_eq!(starts(&cfg), [(0x100, 2)]);
+        assert_eq!(cfg.edges, [edge(0x100, 0x105, EdgeKind::Call)]);
+        assert_eq!(
+            cfg.to_dot(Syntax::Intel, &SymbolTable::new()),
+            "digraph sub_0100 {\n    \
+             node [shape=box, fontname=\"monospace\"];\n    \
+             sub_0100 [label=\"sub_0100:\\l0100  call sub_0105\\l0103  ret\\l\"];\n    \
+             sub_0105 [shape=ellipse, label=\"sub_0105\"];\n    \
+             sub_0100 -> sub_0105 [label=\"call\", style=dashed];\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn jumps_out_of_the_image_end_in_an_ellipse() {
+        // jmp 0x1000
+        let image = [0xE9, 0xFD, 0x0E];
+        let cfg = build(&image, 0x100, 0x100);
+
+        assert_eq!(starts(&cfg), [(0x100, 1)]);
+        assert_eq!(cfg.edges, [edge(0x100, 0x1000, EdgeKind::Taken)]);
+        let mut symbols = SymbolTable::new();
+        symbols.insert("exit", 0x1000);
+        let dot = cfg.to_dot(Syntax::Intel, &symbols);
+        assert!(dot.contains("    sub_0100 [label=\"sub_0100:\\l0100  jmp near exit\\l\"];\n"));
+        assert!(dot.contains("    loc_1000 [shape=ellipse, label=\"exit\"];\n"));
+        assert!(dot.contains("    sub_0100 -> loc_1000 [label=\"taken\", color=darkgreen];\n"));
+    }
+}
@@ -1,7 +1,8 @@
-use crate::core::decoder::decode;
+use crate::core::decoder::{DecodeError, decode};
+use crate::core::instruction::{Instruction, Operand, Register, SegmentRegister};
 use bitflags::bitflags;
 bitflags! {
-    #[derive(Debug, Clone, Copy)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
     pub struct Flags: u16 {
         const CARRY           = 0x0001; // Bit 0
         const PARITY          = 0x0004; // Bit 2
@@ -15,7 +16,9 @@ bitflags! {
     }
 }
 
+// Only the executor will read most of these, for now they're just printed
 #[derive(Debug, Default)]
+#[allow(dead_code)]
 pub struct Registers {
     // General purpose registers
     pub ax: u16,
@@ -41,6 +44,7 @@ pub struct Registers {
     pub ip: u16,
 }
 
+#[allow(dead_code)] // the byte halves wait on the executor
 impl Registers {
     pub fn al(&self) -> u8 {
         self.ax as u8
@@ -99,6 +103,37 @@ impl Registers {
     pub fn set_dh(&mut self, value: u8) {
         self.dx = (self.dx & 0x00FF) | ((value as u16) << 8);
     }
+
+    // Byte registers come back zero-extended
+    pub fn get(&self, reg: Register) -> u16 {
+        match reg {
+            Register::AL => self.al() as u16,
+            Register::CL => self.cl() as u16,
+            Register::DL => self.dl() as u16,
+            Register::BL => self.bl() as u16,
+            Register::AH => self.ah() as u16,
+            Register::CH => self.ch() as u16,
+            Register::DH => self.dh() as u16,
+            Register::BH => self.bh() as u16,
+            Register::AX => self.ax,
+            Register::CX => self.cx,
+            Register::DX => self.dx,
+            Register::BX => self.bx,
+            Register::SP => self.sp,
+            Register::BP => self.bp,
+            Register::SI => self.si,
+            Register::DI => self.di,
+        }
+    }
+
+    pub fn get_segment(&self, sreg: SegmentRegister) -> u16 {
+        match sreg {
+            SegmentRegister::ES => self.es,
+            SegmentRegister::CS => self.cs,
+            SegmentRegister::SS => self.ss,
+            SegmentRegister::DS => self.ds,
+        }
+    }
 }
 
 pub struct Cpu {
@@ -110,7 +145,7 @@ impl Cpu {
     pub fn new() -> Self {
         Cpu {
             regs: Registers {
-                sp: 0xFFFe,
+                sp: 0xFFFE,
                 ..Default::default()
             },
             memory: Box::new([0; 1024 * 1024]),
@@ -131,8 +166,34 @@ impl Cpu {
         self.regs.ss = seg;
     }
 
+    // The 8086 only has 20 address lines, so FFFF:0010 wraps around to 0
     pub fn get_physical_address(segment: u16, offset: u16) -> u32 {
-        ((segment as u32) << 4) + (offset as u32)
+        (((segment as u32) << 4) + (offset as u32)) & 0xFFFFF
+    }
+
+    // Segment and offset a memory operand refers to. The offset wraps at
+    // 64 KiB, and addressing through BP defaults to SS instead of DS. An
+    // override prefix, recorded on the operand, beats either default. None
+    // for anything but a memory operand.
+    pub fn effective_address(&self, operand: &Operand) -> Option<(SegmentRegister, u16)> {
+        let Operand::Mem(mem) = operand else {
+            return None;
+        };
+        let base = mem.base.map_or(0, |reg| self.regs.get(reg));
+        let index = mem.index.map_or(0, |reg| self.regs.get(reg));
+        let offset = base
+            .wrapping_add(index)
+            .wrapping_add(mem.disp.value() as u16);
+        Some((mem.effective_segment(), offset))
+    }
+
+    #[allow(dead_code)]
+    pub fn operand_address(&self, operand: &Operand) -> Option<u32> {
+        let (segment, offset) = self.effective_address(operand)?;
+        Some(Cpu::get_physical_address(
+            self.regs.get_segment(segment),
+            offset,
+        ))
     }
 
     pub fn read_byte(&self, addr: u32) -> u8 {
@@ -141,7 +202,7 @@ impl Cpu {
 
     pub fn read_word(&self, addr: u32) -> u16 {
         let low = self.read_byte(addr);
-        let high = self.read_byte(addr + 1);
+        let high = self.read_byte((addr + 1) & 0xFFFFF);
         u16::from_le_bytes([low, high])
     }
 
@@ -150,17 +211,19 @@ impl Cpu {
     }
 
     pub fn write_word(&mut self, addr: u32, val: u16) {
-        self.memory[addr as usize] = val as u8;
-        self.memory[(addr + 1) as usize] = (val >> 8) as u8;
+        self.write_byte(addr, val as u8);
+        self.write_byte((addr + 1) & 0xFFFFF, (val >> 8) as u8);
     }
 
     // The Corrected Stack Logic
+    #[allow(dead_code)]
     pub fn push(&mut self, val: u16) {
         self.regs.sp = self.regs.sp.wrapping_sub(2);
         let addr = Cpu::get_physical_address(self.regs.ss, self.regs.sp);
         self.write_word(addr, val);
     }
 
+    #[allow(dead_code)]
     pub fn pop(&mut self) -> u16 {
         let addr = Cpu::get_physical_address(self.regs.ss, self.regs.sp);
         let val = self.read_word(addr);
@@ -168,8 +231,84 @@ impl Cpu {
         val
     }
 
-    pub fn step(&mut self) {
-        let ins = decode(self, &Cpu::get_physical_address(self.regs.cs, self.regs.ip));
-        println!("Decoded Instruction: {:#?}", ins)
+    // Decodes the instruction at CS:IP and moves IP past it
+    pub fn step(&mut self) -> Result<Instruction, DecodeError> {
+        // IP is left on the offending instruction when decoding fails
+        let addr = Cpu::get_physical_address(self.regs.cs, self.regs.ip);
+        let ins = decode(self, addr, self.regs.ip)?;
+        self.regs.ip = self.regs.ip.wrapping_add(ins.length as u16);
+        Ok(ins)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::instruction::*;
+
+    fn mem(base: Option<Register>, segment: Option<SegmentRegister>) -> Operand {
+        Operand::Mem(Memory {
+            base,
+            index: Some(Register::SI),
+            disp: Displacement::Byte(-2),
+            segment,
+            width: Width::Word,
+        })
+    }
+
+    #[test]
+    fn effective_address_picks_the_segment() {
+        let mut cpu = Cpu::new();
+        cpu.regs.bx = 0x10;
+        cpu.regs.bp = 0x20;
+        cpu.regs.si = 0x1;
+        let bx = mem(Some(Register::BX), None);
+        let bp = mem(Some(Register::BP), None);
+        let es_bp = mem(Some(Register::BP), Some(SegmentRegister::ES));
+        assert_eq!(cpu.effective_address(&bx), Some((SegmentRegister::DS, 0xF)));
+        assert_eq!(
+            cpu.effective_address(&bp),
+            Some((SegmentRegister::SS, 0x1F))
+        );
+        assert_eq!(
+            cpu.effective_address(&es_bp),
+            Some((SegmentRegister::ES, 0x1F))
+        );
+    }
+
+    #[test]
+    fn effective_address_wraps_the_offset() {
+        let cpu = Cpu::new();
+        let operand = mem(None, None);
+        assert_eq!(
+            cpu.effective_address(&operand),
+            Some((SegmentRegister::DS, 0xFFFE))
+        );
+    }
+
+    #[test]
+    fn only_memory_has_an_address() {
+        let cpu = Cpu::new();
+        assert_eq!(cpu.effective_address(&Operand::Reg(Register::AX)), None);
+        assert_eq!(cpu.operand_address(&Operand::Imm(Immediate::Byte(1))), None);
+    }
+
+    #[test]
+    fn physical_addresses_wrap_at_1mib() {
+        let mut cpu = Cpu::new();
+        cpu.regs.ds = 0xFFFF;
+        cpu.regs.bx = 0x10;
+        let operand = Operand::Mem(Memory {
+            base: Some(Register::BX),
+            index: None,
+            disp: Displacement::None,
+            segment: None,
+            width: Width::Word,
+        });
+        assert_eq!(Cpu::get_physical_address(0xFFFF, 0x0010), 0);
+        assert_eq!(cpu.operand_address(&operand), Some(0));
+        cpu.write_word(0xFFFFF, 0x1234);
+        assert_eq!(cpu.read_word(0xFFFFF), 0x1234);
+        assert_eq!(cpu.read_byte(0), 0x12);
     }
 }
@@ -0,0 +1,38 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeErrorKind {
+    // The ModRM byte selects a form the instruction does not have,
+    // e.g. LEA with a register operand or FEh with REG >= 2
+    InvalidModRM,
+    // The source ended in the middle of the instruction
+    Truncated,
+    // More bytes than Instruction::length can count, only possible with a
+    // long run of prefixes
+    TooLong,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+    pub address: u32,
+    // Bytes of the instruction read up to the point of failure
+    pub bytes: Vec<u8>,
+    pub kind: DecodeErrorKind,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.kind {
+            DecodeErrorKind::InvalidModRM => "invalid ModRM for the instruction",
+            DecodeErrorKind::Truncated => "truncated instruction",
+            DecodeErrorKind::TooLong => "instruction longer than 255 bytes",
+        };
+        write!(f, "{} at 0x{:05X}:", reason, self.address)?;
+        for byte in &self.bytes {
+            write!(f, " {:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for DecodeError {}
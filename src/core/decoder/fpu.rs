@@ -0,0 +1,308 @@
+use crate::core::decoder::Operation;
+use crate::core::decoder::error::DecodeError;
+use crate::core::decoder::source::Reader;
+use crate::core::decoder::utils::{decode_modrm_byte, decode_rm_operand};
+use crate::core::instruction::*;
+
+use Mnemonic as Op;
+
+const ARITH: [Mnemonic; 8] = [
+    Op::Fadd,
+    Op::Fmul,
+    Op::Fcom,
+    Op::Fcomp,
+    Op::Fsub,
+    Op::Fsubr,
+    Op::Fdiv,
+    Op::Fdivr,
+];
+
+const INT_ARITH: [Mnemonic; 8] = [
+    Op::Fiadd,
+    Op::Fimul,
+    Op::Ficom,
+    Op::Ficomp,
+    Op::Fisub,
+    Op::Fisubr,
+    Op::Fidiv,
+    Op::Fidivr,
+];
+
+// 8087 meaning of ESC opcode + REG field when R/M is a memory operand, along
+// with the width of what it holds
+pub fn memory_operation(opcode: u8, reg: u8) -> Option<(Mnemonic, Width)> {
+    let reg = reg as usize;
+    match (opcode, reg) {
+        (0xD8, _) => Some((ARITH[reg], Width::Dword)),
+        (0xDA, _) => Some((INT_ARITH[reg], Width::Dword)),
+        (0xDC, _) => Some((ARITH[reg], Width::Qword)),
+        (0xDE, _) => Some((INT_ARITH[reg], Width::Word)),
+
+        (0xD9, 0) => Some((Op::Fld, Width::Dword)),
+        (0xD9, 2) => Some((Op::Fst, Width::Dword)),
+        (0xD9, 3) => Some((Op::Fstp, Width::Dword)),
+        (0xD9, 4) => Some((Op::Fldenv, Width::Environment)),
+        (0xD9, 5) => Some((Op::Fldcw, Width::Word)),
+        (0xD9, 6) => Some((Op::Fnstenv, Width::Environment)),
+        (0xD9, 7) => Some((Op::Fnstcw, Width::Word)),
+
+        (0xDB, 0) => Some((Op::Fild, Width::Dword)),
+        (0xDB, 2) => Some((Op::Fist, Width::Dword)),
+        (0xDB, 3) => Some((Op::Fistp, Width::Dword)),
+        (0xDB, 5) => Some((Op::Fld, Width::Tword)),
+        (0xDB, 7) => Some((Op::Fstp, Width::Tword)),
+
+        (0xDD, 0) => Some((Op::Fld, Width::Qword)),
+        (0xDD, 2) => Some((Op::Fst, Width::Qword)),
+        (0xDD, 3) => Some((Op::Fstp, Width::Qword)),
+        (0xDD, 4) => Some((Op::Frstor, Width::State)),
+        (0xDD, 6) => Some((Op::Fnsave, Width::State)),
+        (0xDD, 7) => Some((Op::Fnstsw, Width::Word)),
+
+        (0xDF, 0) => Some((Op::Fild, Width::Word)),
+        (0xDF, 2) => Some((Op::Fist, Width::Word)),
+        (0xDF, 3) => Some((Op::Fistp, Width::Word)),
+        (0xDF, 4) => Some((Op::Fbld, Width::Tword)),
+        (0xDF, 5) => Some((Op::Fild, Width::Qword)),
+        (0xDF, 6) => Some((Op::Fbstp, Width::Tword)),
+        (0xDF, 7) => Some((Op::Fistp, Width::Qword)),
+        _ => None,
+    }
+}
+
+// 8087 meaning of ESC opcode + REG field when R/M selects ST(i)
+pub fn register_operation(opcode: u8, reg: u8, sti: u8) -> Option<Operation> {
+    let st = Operand::St(sti);
+    let st0 = Operand::St(0);
+    match (opcode, reg, sti) {
+        (0xD8, 2 | 3, _) => Some(Operation::one(ARITH[reg as usize], st)),
+        (0xD8, _, _) => Some(Operation::two(ARITH[reg as usize], st0, st)),
+
+        (0xD9, 0, _) => Some(Operation::one(Op::Fld, st)),
+        (0xD9, 1, _) => Some(Operation::one(Op::Fxch, st)),
+        (0xD9, 2, 0) => Some(Operation::bare(Op::Fnop)),
+        (0xD9, 4, 0) => Some(Operation::bare(Op::Fchs)),
+        (0xD9, 4, 1) => Some(Operation::bare(Op::Fabs)),
+        (0xD9, 4, 4) => Some(Operation::bare(Op::Ftst)),
+        (0xD9, 4, 5) => Some(Operation::bare(Op::Fxam)),
+        (0xD9, 5, 0) => Some(Operation::bare(Op::Fld1)),
+        (0xD9, 5, 1) => Some(Operation::bare(Op::Fldl2t)),
+        (0xD9, 5, 2) => Some(Operation::bare(Op::Fldl2e)),
+        (0xD9, 5, 3) => Some(Operation::bare(Op::Fldpi)),
+        (0xD9, 5, 4) => Some(Operation::bare(Op::Fldlg2)),
+        (0xD9, 5, 5) => Some(Operation::bare(Op::Fldln2)),
+        (0xD9, 5, 6) => Some(Operation::bare(Op::Fldz)),
+        (0xD9, 6, 0) => Some(Operation::bare(Op::F2xm1)),
+        (0xD9, 6, 1) => Some(Operation::bare(Op::Fyl2x)),
+        (0xD9, 6, 2) => Some(Operation::bare(Op::Fptan)),
+        (0xD9, 6, 3) => Some(Operation::bare(Op::Fpatan)),
+        (0xD9, 6, 4) => Some(Operation::bare(Op::Fxtract)),
+        (0xD9, 6, 6) => Some(Operation::bare(Op::Fdecstp)),
+        (0xD9, 6, 7) => Some(Operation::bare(Op::Fincstp)),
+        (0xD9, 7, 0) => Some(Operation::bare(Op::Fprem)),
+        (0xD9, 7, 1) => Some(Operation::bare(Op::Fyl2xp1)),
+        (0xD9, 7, 2) => Some(Operation::bare(Op::Fsqrt)),
+        (0xD9, 7, 4) => Some(Operation::bare(Op::Frndint)),
+        (0xD9, 7, 5) => Some(Operation::bare(Op::Fscale)),
+
+        (0xDB, 4, 0) => Some(Operation::bare(Op::Fneni)),
+        (0xDB, 4, 1) => Some(Operation::bare(Op::Fndisi)),
+        (0xDB, 4, 2) => Some(Operation::bare(Op::Fnclex)),
+        (0xDB, 4, 3) => Some(Operation::bare(Op::Fninit)),
+
+        // With ST(i) as the destination the R bit is flipped: /4 is FSUBR, /5 FSUB
+        (0xDC, 0, _) => Some(Operation::two(Op::Fadd, st, st0)),
+        (0xDC, 1, _) => Some(Operation::two(Op::Fmul, st, st0)),
+        (0xDC, 4, _) => Some(Operation::two(Op::Fsubr, st, st0)),
+        (0xDC, 5, _) => Some(Operation::two(Op::Fsub, st, st0)),
+        (0xDC, 6, _) => Some(Operation::two(Op::Fdivr, st, st0)),
+        (0xDC, 7, _) => Some(Operation::two(Op::Fdiv, st, st0)),
+
+        (0xDD, 0, _) => Some(Operation::one(Op::Ffree, st)),
+        (0xDD, 2, _) => Some(Operation::one(Op::Fst, st)),
+        (0xDD, 3, _) => Some(Operation::one(Op::Fstp, st)),
+
+        (0xDE, 0, _) => Some(Operation::two(Op::Faddp, st, st0)),
+        (0xDE, 1, _) => Some(Operation::two(Op::Fmulp, st, st0)),
+        (0xDE, 3, 1) => Some(Operation::bare(Op::Fcompp)),
+        (0xDE, 4, _) => Some(Operation::two(Op::Fsubrp, st, st0)),
+        (0xDE, 5, _) => Some(Operation::two(Op::Fsubp, st, st0)),
+        (0xDE, 6, _) => Some(Operation::two(Op::Fdivrp, st, st0)),
+        (0xDE, 7, _) => Some(Operation::two(Op::Fdivp, st, st0)),
+        _ => None,
+    }
+}
+
+pub fn decode_esc(reader: &mut Reader, opcode: u8) -> Result<Operation, DecodeError> {
+    // Opcode --- ModRM --- DispL --- DispH === Max 4 bytes
+    let modrm_byte = reader.next_byte()?;
+    let modrm = decode_modrm_byte(modrm_byte);
+
+    let decoded = if modrm.is_reg() {
+        register_operation(opcode, modrm.reg, modrm.rm)
+    } else {
+        match memory_operation(opcode, modrm.reg) {
+            Some((mnemonic, width)) => {
+                let mem = decode_rm_operand(reader, &modrm, width)?;
+                Some(Operation::one(mnemonic, mem))
+            }
+            None => None,
+        }
+    };
+
+    match decoded {
+        Some(operation) => Ok(operation),
+        None => {
+            // The low 3 opcode bits and the REG field form the 6-bit ESC number
+            let esc_number = ((opcode & 0b111) << 3) | modrm.reg;
+            let rm = if modrm.is_reg() {
+                Operand::St(modrm.rm)
+            } else {
+                decode_rm_operand(reader, &modrm, Width::Word)?
+            };
+            Ok(Operation::two(
+                Mnemonic::Esc,
+                Operand::Imm(Immediate::Byte(esc_number)),
+                rm,
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::decoder::decode_bytes;
+
+    // mnemonic and operands of the single instruction in the bytes, which
+    // it has to cover exactly
+    fn decode_all(bytes: &[u8]) -> (Mnemonic, [Option<Operand>; 2]) {
+        let ins = decode_bytes(bytes, 0x100).unwrap();
+        assert_eq!(ins.length as usize, bytes.len(), "{:02X?}", bytes);
+        (ins.mnemonic, ins.operands)
+    }
+
+    fn bx(width: Width) -> Option<Operand> {
+        Some(Operand::Mem(Memory {
+            base: Some(Register::BX),
+            index: None,
+            disp: Displacement::None,
+            segment: None,
+            width,
+        }))
+    }
+
+    fn st(i: u8) -> Option<Operand> {
+        Some(Operand::St(i))
+    }
+
+    #[test]
+    fn decodes_memory_widths() {
+        assert_eq!(
+            decode_all(&[0xD9, 0x07]),
+            (Op::Fld, [bx(Width::Dword), None])
+        );
+        assert_eq!(
+            decode_all(&[0xDD, 0x07]),
+            (Op::Fld, [bx(Width::Qword), None])
+        );
+        assert_eq!(
+            decode_all(&[0xDB, 0x2F]),
+            (Op::Fld, [bx(Width::Tword), None])
+        );
+        assert_eq!(
+            decode_all(&[0xDF, 0x07]),
+            (Op::Fild, [bx(Width::Word), None])
+        );
+        assert_eq!(
+            decode_all(&[0xDB, 0x07]),
+            (Op::Fild, [bx(Width::Dword), None])
+        );
+        assert_eq!(
+            decode_all(&[0xDF, 0x2F]),
+            (Op::Fild, [bx(Width::Qword), None])
+        );
+        assert_eq!(
+            decode_all(&[0xDF, 0x27]),
+            (Op::Fbld, [bx(Width::Tword), None])
+        );
+        assert_eq!(
+            decode_all(&[0xDD, 0x3F]),
+            (Op::Fnstsw, [bx(Width::Word), None])
+        );
+        assert_eq!(
+            decode_all(&[0xD9, 0x3F]),
+            (Op::Fnstcw, [bx(Width::Word), None])
+        );
+        assert_eq!(
+            decode_all(&[0xDD, 0x37]),
+            (Op::Fnsave, [bx(Width::State), None])
+        );
+        assert_eq!(
+            decode_all(&[0xD9, 0x27]),
+            (Op::Fldenv, [bx(Width::Environment), None])
+        );
+        assert_eq!(
+            decode_all(&[0xDE, 0x07]),
+            (Op::Fiadd, [bx(Width::Word), None])
+        );
+        assert_eq!(
+            decode_all(&[0xDC, 0x37]),
+            (Op::Fdiv, [bx(Width::Qword), None])
+        );
+        // Displacements are read like any other ModRM operand
+        let (_, operands) = decode_all(&[0xDD, 0x87, 0x34, 0x12]);
+        assert!(matches!(
+            operands[0],
+            Some(Operand::Mem(Memory {
+                disp: Displacement::Word(0x1234),
+                width: Width::Qword,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn decodes_register_forms() {
+        assert_eq!(decode_all(&[0xD8, 0xC1]), (Op::Fadd, [st(0), st(1)]));
+        assert_eq!(decode_all(&[0xD8, 0xD9]), (Op::Fcomp, [st(1), None]));
+        assert_eq!(decode_all(&[0xD9, 0xC9]), (Op::Fxch, [st(1), None]));
+        assert_eq!(decode_all(&[0xD9, 0xE8]), (Op::Fld1, [None, None]));
+        assert_eq!(decode_all(&[0xDB, 0xE3]), (Op::Fninit, [None, None]));
+        assert_eq!(decode_all(&[0xDE, 0xD9]), (Op::Fcompp, [None, None]));
+    }
+
+    #[test]
+    fn flips_subtract_and_divide_into_st_i() {
+        // D8h: ST(0) = ST(0) op ST(i), /4 FSUB, /5 FSUBR
+        assert_eq!(decode_all(&[0xD8, 0xE1]), (Op::Fsub, [st(0), st(1)]));
+        assert_eq!(decode_all(&[0xD8, 0xE9]), (Op::Fsubr, [st(0), st(1)]));
+        // DCh and DEh: ST(i) = ST(i) op ST(0), with /4 and /5 swapped
+        assert_eq!(decode_all(&[0xDC, 0xE1]), (Op::Fsubr, [st(1), st(0)]));
+        assert_eq!(decode_all(&[0xDC, 0xE9]), (Op::Fsub, [st(1), st(0)]));
+        assert_eq!(decode_all(&[0xDC, 0xF1]), (Op::Fdivr, [st(1), st(0)]));
+        assert_eq!(decode_all(&[0xDC, 0xF9]), (Op::Fdiv, [st(1), st(0)]));
+        assert_eq!(decode_all(&[0xDE, 0xE1]), (Op::Fsubrp, [st(1), st(0)]));
+        assert_eq!(decode_all(&[0xDE, 0xE9]), (Op::Fsubp, [st(1), st(0)]));
+        assert_eq!(decode_all(&[0xDE, 0xF1]), (Op::Fdivrp, [st(1), st(0)]));
+        assert_eq!(decode_all(&[0xDE, 0xF9]), (Op::Fdivp, [st(1), st(0)]));
+    }
+
+    #[test]
+    fn falls_back_to_esc() {
+        let esc = |n| Some(Operand::Imm(Immediate::Byte(n)));
+        // D9h /1 has no memory form, DDh /5 neither
+        assert_eq!(
+            decode_all(&[0xD9, 0x0F]),
+            (Op::Esc, [esc(0x09), bx(Width::Word)])
+        );
+        assert_eq!(
+            decode_all(&[0xDD, 0x2F]),
+            (Op::Esc, [esc(0x2D), bx(Width::Word)])
+        );
+        // Unassigned register forms keep ST(i) as the operand
+        assert_eq!(decode_all(&[0xD9, 0xD1]), (Op::Esc, [esc(0x0A), st(1)]));
+        assert_eq!(decode_all(&[0xDB, 0xC0]), (Op::Esc, [esc(0x18), st(0)]));
+        assert_eq!(decode_all(&[0xDE, 0xD8]), (Op::Esc, [esc(0x33), st(0)]));
+    }
+}
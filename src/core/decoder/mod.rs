@@ -1,59 +1,600 @@
-mod ascii_decimal;
-mod convert;
-mod flags;
-mod in_out;
-mod interrupt;
-mod jump;
-mod load;
-mod loop_set;
-mod mov;
-mod nop;
+mod error;
+mod fpu;
+mod operands;
 mod prefix;
-mod stack;
-mod subroutine;
+mod source;
 mod utils;
-mod xlat;
 
-use crate::core::cpu::Cpu;
+pub use error::DecodeError;
+pub use fpu::{memory_operation, register_operation};
+pub use prefix::apply_prefix;
+pub use source::ByteSource;
+
 use crate::core::instruction::*;
+use crate::core::opcodes::{OPCODES, Opcode};
+use error::DecodeErrorKind;
+use source::Reader;
+use utils::decode_modrm_byte;
+
+// An opcode's mnemonic and operands. decode() adds the prefixes, opcode and
+// length to turn it into an Instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Operation {
+    pub mnemonic: Mnemonic,
+    pub operands: [Option<Operand>; 2],
+}
+
+impl Operation {
+    pub fn bare(mnemonic: Mnemonic) -> Self {
+        Operation {
+            mnemonic,
+            operands: [None, None],
+        }
+    }
+
+    pub fn one(mnemonic: Mnemonic, operand: Operand) -> Self {
+        Operation {
+            mnemonic,
+            operands: [Some(operand), None],
+        }
+    }
+
+    pub fn two(mnemonic: Mnemonic, dest: Operand, src: Operand) -> Self {
+        Operation {
+            mnemonic,
+            operands: [Some(dest), Some(src)],
+        }
+    }
+}
+
+// Decodes the instruction starting at addr in source. ip is the offset the
+// instruction runs at, relative targets are resolved against it. Nothing is
+// modified, the caller advances past the instruction using its length.
+pub fn decode(source: &dyn ByteSource, addr: u32, ip: u16) -> Result<Instruction, DecodeError> {
+    let mut reader = Reader::new(source, addr, ip);
+    let (prefixes, prefix_bytes) = prefix::decode_prefixes(&mut reader)?;
+    let opcode = reader.next_byte()?;
+    let has_modrm = match &OPCODES[opcode as usize] {
+        Opcode::Op(info) => info.encoding.has_modrm(),
+        Opcode::Group(_) | Opcode::Esc => true,
+        Opcode::Prefix => false,
+    };
+    let modrm = if has_modrm {
+        Some(reader.peek_byte()?)
+    } else {
+        None
+    };
+    let Operation {
+        mnemonic,
+        mut operands,
+    } = decode_operation(&mut reader, opcode)?;
+
+    // The override applies to whichever operand addresses memory
+    if let Some(segment) = prefixes.segment {
+        for operand in operands.iter_mut().flatten() {
+            if let Operand::Mem(mem) = operand {
+                mem.segment = Some(segment);
+            }
+        }
+    }
+
+    Ok(Instruction {
+        prefixes,
+        prefix_bytes,
+        opcode,
+        modrm,
+        mnemonic,
+        operands,
+        length: reader.length(),
+    })
+}
+
+// Decodes from the start of bytes, as if they were loaded at offset ip
+pub fn decode_bytes(bytes: &[u8], ip: u16) -> Result<Instruction, DecodeError> {
+    decode(&bytes, 0, ip)
+}
+
+fn decode_operation(reader: &mut Reader, opcode: u8) -> Result<Operation, DecodeError> {
+    match &OPCODES[opcode as usize] {
+        Opcode::Op(info) => Ok(Operation {
+            mnemonic: info.mnemonic,
+            operands: operands::decode_operands(reader, opcode, info, None)?,
+        }),
+        Opcode::Group(group) => {
+            let modrm = decode_modrm_byte(reader.next_byte()?);
+            let Some(info) = &group[modrm.reg as usize] else {
+                return Err(reader.error(DecodeErrorKind::InvalidModRM));
+            };
+            Ok(Operation {
+                mnemonic: info.mnemonic,
+                operands: operands::decode_operands(reader, opcode, info, Some(modrm))?,
+            })
+        }
+        Opcode::Esc => fpu::decode_esc(reader, opcode),
+        Opcode::Prefix => unreachable!("Prefix 0x{:02X} is consumed by decode_prefixes", opcode),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Mnemonic::*;
+    use Register::*;
+
+    fn error(bytes: &[u8]) -> DecodeError {
+        decode_bytes(bytes, 0x100).unwrap_err()
+    }
+
+    // Mnemonic, operands and length of the instruction the bytes start with
+    fn decoded(bytes: &[u8]) -> (Mnemonic, [Option<Operand>; 2], u8) {
+        let ins = decode_bytes(bytes, 0x100).unwrap();
+        (ins.mnemonic, ins.operands, ins.length)
+    }
 
-pub fn decode(cpu: &mut Cpu, addr: &u32) -> Instruction {
-    let opcode = cpu.read_byte(*addr);
-    match opcode {
-        0xB0..=0xBF | 0x8E | 0xC6 | 0xC7 | 0xA0..=0xA3 | 0x88..=0x8C => mov::decode_mov(cpu, addr),
-        0x2E | 0x3E | 0x26 | 0x36 => prefix::decode_seg_override(cpu, addr),
-        0xE4 | 0xE5 | 0xEC | 0xED => in_out::decode_in(cpu, addr),
-        0xE6 | 0xE7 | 0xEE | 0xEF => in_out::decode_out(cpu, addr),
-        0xC3 | 0xCB | 0xC2 | 0xCA => subroutine::decode_ret(cpu, addr),
-        0xF8 | 0xFC | 0xFA => flags::decode_clear_flags(cpu, addr),
-        0xF9 | 0xFD | 0xFB => flags::decode_store_flags(cpu, addr),
-        0xE0 | 0xE1 | 0xE2 => loop_set::decode_loop_set(cpu, addr),
-        0xCC | 0xCD | 0xCE => interrupt::decode_int(cpu, addr),
-        0xC4 | 0xC5 | 0x8D => load::decode_load_pointer(cpu, addr),
-        0x70..=0x7F => jump::decode_jcond(cpu, addr),
-        0xF3 | 0xF2 => prefix::decode_rep(cpu, addr),
-        0xE3 => jump::decode_jcxz(cpu, addr),
-        0xF4 => nop::decode_hlt(cpu, addr),
-        0x90 => nop::decode_nop(cpu, addr),
-        0x98 => convert::decode_cbw(cpu, addr),
-        0x99 => convert::decode_cwd(cpu, addr),
-        0x37 => ascii_decimal::decode_aaa(cpu, addr),
-        0xD5 => ascii_decimal::decode_aad(cpu, addr),
-        0xD4 => ascii_decimal::decode_aam(cpu, addr),
-        0x3F => ascii_decimal::decode_aas(cpu, addr),
-        0x27 => ascii_decimal::decode_daa(cpu, addr),
-        0x2F => ascii_decimal::decode_das(cpu, addr),
-        0xF5 => flags::decode_cmc(cpu, addr),
-        0xCF => interrupt::decode_iret(cpu, addr),
-        0x9E => flags::decode_sahf(cpu, addr),
-        0x9F => flags::decode_lahf(cpu, addr),
-        0xF0 => prefix::decode_lock(cpu, addr),
-        0x9B => nop::decode_wait(cpu, addr),
-        0x9C => stack::decode_pushf(cpu, addr),
-        0x9D => stack::decode_popf(cpu, addr),
-        0xD7 => xlat::decode_xlat(cpu, addr),
-        _ => {
-            unimplemented!("TODO: Unknown opcode: 0x{:2X}", opcode)
+    fn reg(reg: Register) -> Option<Operand> {
+        Some(Operand::Reg(reg))
+    }
+
+    fn imm(imm: Immediate) -> Option<Operand> {
+        Some(Operand::Imm(imm))
+    }
+
+    fn mem(base: Option<Register>, disp: Displacement, width: Width) -> Option<Operand> {
+        Some(Operand::Mem(Memory {
+            base,
+            index: None,
+            disp,
+            segment: None,
+            width,
+        }))
+    }
+
+    // [bx] of the given width
+    fn bx(width: Width) -> Option<Operand> {
+        mem(Some(BX), Displacement::None, width)
+    }
+
+    #[test]
+    fn decodes_alu_forms() {
+        let ops = [Add, Or, Adc, Sbb, And, Sub, Xor, Cmp];
+        for (i, op) in ops.into_iter().enumerate() {
+            let base = i as u8 * 8;
+            // r/m8, reg8 and r/m16, reg16
+            assert_eq!(decoded(&[base, 0xD8]), (op, [reg(AL), reg(BL)], 2));
+            assert_eq!(
+                decoded(&[base + 1, 0x47, 0x02]),
+                (
+                    op,
+                    [mem(Some(BX), Displacement::Byte(2), Width::Word), reg(AX)],
+                    3
+                )
+            );
+            // reg8, r/m8 and reg16, r/m16
+            assert_eq!(decoded(&[base + 2, 0xC3]), (op, [reg(AL), reg(BL)], 2));
+            assert_eq!(
+                decoded(&[base + 3, 0x87, 0x34, 0x12]),
+                (
+                    op,
+                    [
+                        reg(AX),
+                        mem(Some(BX), Displacement::Word(0x1234), Width::Word)
+                    ],
+                    4
+                )
+            );
+            // Accumulator and immediate
+            assert_eq!(
+                decoded(&[base + 4, 0x12]),
+                (op, [reg(AL), imm(Immediate::Byte(0x12))], 2)
+            );
+            assert_eq!(
+                decoded(&[base + 5, 0x34, 0x12]),
+                (op, [reg(AX), imm(Immediate::Word(0x1234))], 3)
+            );
         }
+        // A direct address always has a 16-bit displacement
+        assert_eq!(
+            decoded(&[0x13, 0x06, 0x34, 0x12]),
+            (
+                Adc,
+                [reg(AX), mem(None, Displacement::Word(0x1234), Width::Word)],
+                4
+            )
+        );
+    }
+
+    #[test]
+    fn decodes_the_immediate_group() {
+        let ops = [Add, Or, Adc, Sbb, And, Sub, Xor, Cmp];
+        for (reg_field, op) in ops.into_iter().enumerate() {
+            let modrm = 0xC3 | (reg_field as u8) << 3;
+            assert_eq!(
+                decoded(&[0x80, modrm, 0x12]),
+                (op, [reg(BL), imm(Immediate::Byte(0x12))], 3)
+            );
+            assert_eq!(
+                decoded(&[0x81, modrm, 0x34, 0x12]),
+                (op, [reg(BX), imm(Immediate::Word(0x1234))], 4)
+            );
+            // 82h is an alias of 80h
+            assert_eq!(
+                decoded(&[0x82, modrm, 0x12]),
+                (op, [reg(BL), imm(Immediate::Byte(0x12))], 3)
+            );
+            assert_eq!(
+                decoded(&[0x83, modrm, 0x12]),
+                (op, [reg(BX), imm(Immediate::SignExtended(0x12))], 3)
+            );
+        }
+        // 83h sign-extends its byte, so add word [bx], -1
+        assert_eq!(
+            decoded(&[0x83, 0x07, 0xFF]),
+            (Add, [bx(Width::Word), imm(Immediate::SignExtended(-1))], 3)
+        );
+        assert_eq!(
+            decoded(&[0x83, 0x67, 0x02, 0x80]),
+            (
+                And,
+                [
+                    mem(Some(BX), Displacement::Byte(2), Width::Word),
+                    imm(Immediate::SignExtended(-128))
+                ],
+                4
+            )
+        );
+        // Memory operand and immediate both follow the ModRM byte
+        assert_eq!(
+            decoded(&[0x81, 0xAF, 0x00, 0x01, 0x34, 0x12]),
+            (
+                Sub,
+                [
+                    mem(Some(BX), Displacement::Word(0x100), Width::Word),
+                    imm(Immediate::Word(0x1234))
+                ],
+                6
+            )
+        );
+        assert_eq!(
+            decoded(&[0x80, 0x3F, 0x12]),
+            (Cmp, [bx(Width::Byte), imm(Immediate::Byte(0x12))], 3)
+        );
+    }
+
+    #[test]
+    fn decodes_the_shift_group() {
+        let one = imm(Immediate::Byte(1));
+        let ops = [Rol, Ror, Rcl, Rcr, Shl, Shr, Setmo, Sar];
+        for (reg_field, op) in ops.into_iter().enumerate() {
+            let modrm = 0xC3 | (reg_field as u8) << 3;
+            assert_eq!(decoded(&[0xD0, modrm]), (op, [reg(BL), one], 2));
+            assert_eq!(decoded(&[0xD1, modrm]), (op, [reg(BX), one], 2));
+            assert_eq!(decoded(&[0xD2, modrm]), (op, [reg(BL), reg(CL)], 2));
+            assert_eq!(decoded(&[0xD3, modrm]), (op, [reg(BX), reg(CL)], 2));
+        }
+        assert_eq!(decoded(&[0xD1, 0x27]), (Shl, [bx(Width::Word), one], 2));
+        assert_eq!(
+            decoded(&[0xD2, 0x4F, 0x02]),
+            (
+                Ror,
+                [mem(Some(BX), Displacement::Byte(2), Width::Byte), reg(CL)],
+                3
+            )
+        );
+        // REG 6 is the undocumented SETMO
+        assert_eq!(decoded(&[0xD0, 0xF0]), (Setmo, [reg(AL), one], 2));
+        assert_eq!(
+            decoded(&[0xD3, 0x37]),
+            (Setmo, [bx(Width::Word), reg(CL)], 2)
+        );
+    }
+
+    #[test]
+    fn decodes_the_unary_group() {
+        // TEST is the only one with an immediate, and /1 repeats it
+        for modrm in [0xC3, 0xCB] {
+            assert_eq!(
+                decoded(&[0xF6, modrm, 0x12]),
+                (Test, [reg(BL), imm(Immediate::Byte(0x12))], 3)
+            );
+            assert_eq!(
+                decoded(&[0xF7, modrm, 0x34, 0x12]),
+                (Test, [reg(BX), imm(Immediate::Word(0x1234))], 4)
+            );
+        }
+        assert_eq!(
+            decoded(&[0xF7, 0x47, 0x02, 0x34, 0x12]),
+            (
+                Test,
+                [
+                    mem(Some(BX), Displacement::Byte(2), Width::Word),
+                    imm(Immediate::Word(0x1234))
+                ],
+                5
+            )
+        );
+        let ops = [Not, Neg, Mul, Imul, Div, Idiv];
+        for (reg_field, op) in (2..).zip(ops) {
+            let modrm = 0xC3 | reg_field << 3;
+            assert_eq!(decoded(&[0xF6, modrm]), (op, [reg(BL), None], 2));
+            assert_eq!(decoded(&[0xF7, modrm]), (op, [reg(BX), None], 2));
+            assert_eq!(
+                decoded(&[0xF7, 0x07 | reg_field << 3]),
+                (op, [bx(Width::Word), None], 2)
+            );
+        }
+    }
+
+    #[test]
+    fn decodes_the_inc_dec_call_jmp_push_group() {
+        assert_eq!(decoded(&[0xFE, 0xC0]), (Inc, [reg(AL), None], 2));
+        assert_eq!(decoded(&[0xFE, 0x0F]), (Dec, [bx(Width::Byte), None], 2));
+        assert_eq!(decoded(&[0xFF, 0x07]), (Inc, [bx(Width::Word), None], 2));
+        assert_eq!(decoded(&[0xFF, 0xC8]), (Dec, [reg(AX), None], 2));
+        // Near indirect through a register or a word, far through a dword
+        assert_eq!(decoded(&[0xFF, 0xD3]), (Call, [reg(BX), None], 2));
+        assert_eq!(decoded(&[0xFF, 0x17]), (Call, [bx(Width::Word), None], 2));
+        assert_eq!(decoded(&[0xFF, 0x1F]), (Call, [bx(Width::Dword), None], 2));
+        assert_eq!(decoded(&[0xFF, 0xE3]), (Jmp, [reg(BX), None], 2));
+        assert_eq!(
+            decoded(&[0xFF, 0x67, 0x02]),
+            (
+                Jmp,
+                [mem(Some(BX), Displacement::Byte(2), Width::Word), None],
+                3
+            )
+        );
+        assert_eq!(
+            decoded(&[0xFF, 0x2E, 0x34, 0x12]),
+            (
+                Jmp,
+                [mem(None, Displacement::Word(0x1234), Width::Dword), None],
+                4
+            )
+        );
+        assert_eq!(decoded(&[0xFF, 0x37]), (Push, [bx(Width::Word), None], 2));
+        assert_eq!(decoded(&[0xFF, 0xF3]), (Push, [reg(BX), None], 2));
+    }
+
+    #[test]
+    fn decodes_single_byte_register_forms() {
+        let regs = [AX, CX, DX, BX, SP, BP, SI, DI];
+        for (i, r) in (0..).zip(regs) {
+            assert_eq!(decoded(&[0x40 + i]), (Inc, [reg(r), None], 1));
+            assert_eq!(decoded(&[0x48 + i]), (Dec, [reg(r), None], 1));
+            assert_eq!(decoded(&[0x50 + i]), (Push, [reg(r), None], 1));
+            assert_eq!(decoded(&[0x58 + i]), (Pop, [reg(r), None], 1));
+            assert_eq!(decoded(&[0x90 + i]), (Xchg, [reg(AX), reg(r)], 1));
+        }
+        // 90h is XCHG AX, AX shown as NOP
+        assert!(decode_bytes(&[0x90], 0x100).unwrap().is_nop());
+        assert!(!decode_bytes(&[0x91], 0x100).unwrap().is_nop());
+
+        let sreg = |s| Some(Operand::SegReg(s));
+        use SegmentRegister::*;
+        assert_eq!(decoded(&[0x06]), (Push, [sreg(ES), None], 1));
+        assert_eq!(decoded(&[0x07]), (Pop, [sreg(ES), None], 1));
+        assert_eq!(decoded(&[0x0E]), (Push, [sreg(CS), None], 1));
+        assert_eq!(decoded(&[0x0F]), (Pop, [sreg(CS), None], 1));
+        assert_eq!(decoded(&[0x16]), (Push, [sreg(SS), None], 1));
+        assert_eq!(decoded(&[0x17]), (Pop, [sreg(SS), None], 1));
+        assert_eq!(decoded(&[0x1E]), (Push, [sreg(DS), None], 1));
+        assert_eq!(decoded(&[0x1F]), (Pop, [sreg(DS), None], 1));
+
+        assert_eq!(decoded(&[0x8F, 0x07]), (Pop, [bx(Width::Word), None], 2));
+        assert_eq!(decoded(&[0x8F, 0xC3]), (Pop, [reg(BX), None], 2));
+    }
+
+    #[test]
+    fn decodes_xchg_and_test() {
+        assert_eq!(decoded(&[0x84, 0xD8]), (Test, [reg(AL), reg(BL)], 2));
+        assert_eq!(
+            decoded(&[0x85, 0x07]),
+            (Test, [bx(Width::Word), reg(AX)], 2)
+        );
+        assert_eq!(decoded(&[0x86, 0xD8]), (Xchg, [reg(AL), reg(BL)], 2));
+        assert_eq!(
+            decoded(&[0x87, 0x87, 0x34, 0x12]),
+            (
+                Xchg,
+                [
+                    mem(Some(BX), Displacement::Word(0x1234), Width::Word),
+                    reg(AX)
+                ],
+                4
+            )
+        );
+        assert_eq!(
+            decoded(&[0xA8, 0x12]),
+            (Test, [reg(AL), imm(Immediate::Byte(0x12))], 2)
+        );
+        assert_eq!(
+            decoded(&[0xA9, 0x34, 0x12]),
+            (Test, [reg(AX), imm(Immediate::Word(0x1234))], 3)
+        );
+
+        // TEST only reads, XCHG reads and writes both sides
+        let access = |bytes: &[u8]| decode_bytes(bytes, 0x100).unwrap().operand_access();
+        use OperandAccess::*;
+        assert_eq!(access(&[0x85, 0x07]), (Read, Read));
+        assert_eq!(access(&[0xA8, 0x12]), (Read, Read));
+        assert_eq!(access(&[0x87, 0x07]), (ReadWrite, ReadWrite));
+    }
+
+    #[test]
+    fn decodes_string_operations_with_their_prefixes() {
+        let ops = [
+            (0xA4, Movsb),
+            (0xA5, Movsw),
+            (0xA6, Cmpsb),
+            (0xA7, Cmpsw),
+            (0xAA, Stosb),
+            (0xAB, Stosw),
+            (0xAC, Lodsb),
+            (0xAD, Lodsw),
+            (0xAE, Scasb),
+            (0xAF, Scasw),
+        ];
+        for (opcode, op) in ops {
+            assert_eq!(decoded(&[opcode]), (op, [None, None], 1));
+        }
+
+        let prefixes = |bytes: &[u8]| {
+            let ins = decode_bytes(bytes, 0x100).unwrap();
+            (ins.prefixes.rep, ins.prefixes.segment, ins.length)
+        };
+        use RepInstruction::*;
+        assert_eq!(prefixes(&[0xA4]), (None, None, 1));
+        assert_eq!(prefixes(&[0xF3, 0xA4]), (Some(Repz), None, 2));
+        assert_eq!(prefixes(&[0xF2, 0xAE]), (Some(Repnz), None, 2));
+        assert_eq!(
+            prefixes(&[0x26, 0xAC]),
+            (None, Some(SegmentRegister::ES), 2)
+        );
+        assert_eq!(
+            prefixes(&[0xF3, 0x2E, 0xA5]),
+            (Some(Repz), Some(SegmentRegister::CS), 3)
+        );
+    }
+
+    #[test]
+    fn decodes_direct_calls_and_jumps() {
+        let rel = |target, disp| Some(Operand::RelTarget { target, disp });
+        let far = |segment, offset| Some(Operand::FarPtr { segment, offset });
+        assert_eq!(
+            decoded(&[0xE8, 0x10, 0x00]),
+            (Call, [rel(0x113, 0x10), None], 3)
+        );
+        assert_eq!(
+            decoded(&[0xE9, 0xFD, 0xFF]),
+            (Jmp, [rel(0x100, -3), None], 3)
+        );
+        assert_eq!(decoded(&[0xEB, 0xFE]), (Jmp, [rel(0x100, -2), None], 2));
+        assert_eq!(decoded(&[0xEB, 0x7F]), (Jmp, [rel(0x181, 0x7F), None], 2));
+        assert_eq!(
+            decoded(&[0x9A, 0x20, 0x00, 0x00, 0x10]),
+            (Call, [far(0x1000, 0x20), None], 5)
+        );
+        assert_eq!(
+            decoded(&[0xEA, 0x20, 0x00, 0x00, 0x10]),
+            (Jmp, [far(0x1000, 0x20), None], 5)
+        );
+
+        // The target moves with CS:IP, the displacement doesn't
+        let target = |bytes: &[u8], cs, ip| {
+            let ins = decode_bytes(bytes, 0x100).unwrap();
+            ins.branch_target(cs, ip)
+        };
+        assert_eq!(
+            target(&[0xE8, 0x10, 0x00], 0x2000, 0x500),
+            Some((0x2000, 0x513))
+        );
+        assert_eq!(target(&[0xEB, 0xFE], 0x2000, 0), Some((0x2000, 0)));
+        assert_eq!(target(&[0xE9, 0x00, 0x80], 0, 0xFFF0), Some((0, 0x7FF3)));
+        assert_eq!(
+            target(&[0xEA, 0x20, 0x00, 0x00, 0x10], 0x2000, 0x500),
+            Some((0x1000, 0x20))
+        );
+        assert_eq!(target(&[0xFF, 0xE3], 0x2000, 0x500), None);
+    }
+
+    #[test]
+    fn decodes_undocumented_aliases_as_their_real_opcodes() {
+        // 60h-6Fh run as 70h-7Fh
+        for opcode in 0x60..=0x6F {
+            let alias = decoded(&[opcode, 0x05]);
+            assert_eq!(alias, decoded(&[opcode + 0x10, 0x05]), "{:02X}", opcode);
+            assert_eq!(alias.2, 2);
+        }
+        assert_eq!(
+            decoded(&[0x64, 0xFE]),
+            (
+                Je,
+                [
+                    Some(Operand::RelTarget {
+                        target: 0x100,
+                        disp: -2
+                    }),
+                    None
+                ],
+                2
+            )
+        );
+        // C0h/C1h and C8h/C9h run as C2h/C3h and CAh/CBh
+        for (alias, opcode) in [(0xC0, 0xC2), (0xC1, 0xC3), (0xC8, 0xCA), (0xC9, 0xCB)] {
+            assert_eq!(
+                decoded(&[alias, 0x04, 0x00]),
+                decoded(&[opcode, 0x04, 0x00]),
+                "{:02X}",
+                alias
+            );
+        }
+        assert_eq!(decoded(&[0xD6]), (Salc, [None, None], 1));
+        // F1h is taken as LOCK
+        let ins = decode_bytes(&[0xF1, 0xA4], 0x100).unwrap();
+        assert_eq!(ins.prefixes.lock, Some(LockPrefix::LockAlias));
+        assert_eq!((ins.mnemonic, ins.length), (Movsb, 2));
+    }
+
+    #[test]
+    fn rejects_forms_the_modrm_byte_cannot_select() {
+        // LEA, LDS and LES with a register operand
+        for opcode in [0x8D, 0xC4, 0xC5] {
+            let err = error(&[opcode, 0xC3]);
+            assert_eq!(err.kind, DecodeErrorKind::InvalidModRM);
+            assert_eq!(err.bytes, [opcode, 0xC3]);
+        }
+        // FEh only has INC and DEC
+        for reg in 2..8 {
+            let err = error(&[0xFE, 0x07 | (reg << 3)]);
+            assert_eq!(err.kind, DecodeErrorKind::InvalidModRM, "FE /{}", reg);
+        }
+        // FFh /7 is undefined, /3 and /5 need a memory operand
+        assert_eq!(error(&[0xFF, 0x3F]).kind, DecodeErrorKind::InvalidModRM);
+        assert_eq!(error(&[0xFF, 0xD8]).kind, DecodeErrorKind::InvalidModRM);
+        assert_eq!(error(&[0xFF, 0xE8]).kind, DecodeErrorKind::InvalidModRM);
+        // MOV to or from a segment register only knows ES, CS, SS and DS
+        for reg in 4..8 {
+            for opcode in [0x8C, 0x8E] {
+                let err = error(&[opcode, 0xC0 | (reg << 3)]);
+                assert_eq!(
+                    err.kind,
+                    DecodeErrorKind::InvalidModRM,
+                    "{:02X} /{}",
+                    opcode,
+                    reg
+                );
+            }
+        }
+        assert!(decode_bytes(&[0x8E, 0xD8], 0x100).is_ok());
+    }
+
+    #[test]
+    fn rejects_instructions_cut_off_by_the_end() {
+        let cases: [&[u8]; 6] = [
+            &[],
+            &[0x26],
+            &[0xB8, 0x34],
+            &[0x8B],
+            &[0x8B, 0x87, 0x34],
+            &[0xEA, 0x00, 0x01, 0x00],
+        ];
+        for bytes in cases {
+            let err = error(bytes);
+            assert_eq!(err.kind, DecodeErrorKind::Truncated, "{:02X?}", bytes);
+            assert_eq!(err.bytes, bytes);
+        }
+        assert_eq!(
+            error(&[0xB8, 0x34]).to_string(),
+            "truncated instruction at 0x00000: B8 34"
+        );
+    }
+
+    #[test]
+    fn rejects_a_prefix_run_too_long_to_count() {
+        let mut bytes = vec![0x26; 255];
+        bytes.push(0x90);
+        let err = error(&bytes);
+        assert_eq!(err.kind, DecodeErrorKind::TooLong);
+        assert_eq!(err.bytes.len(), 255);
     }
 }
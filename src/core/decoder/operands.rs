@@ -0,0 +1,160 @@
+use crate::core::decoder::error::{DecodeError, DecodeErrorKind};
+use crate::core::decoder::source::Reader;
+use crate::core::decoder::utils::{ModRM, decode_modrm_byte, decode_reg, decode_rm_operand};
+use crate::core::instruction::*;
+use crate::core::opcodes::{OpcodeInfo, OperandEncoding};
+
+// Reads whatever follows the opcode according to its table entry. Group
+// opcodes have already consumed the ModRM byte to pick the entry, everything
+// else reads it here when the encoding has one.
+pub fn decode_operands(
+    reader: &mut Reader,
+    opcode: u8,
+    info: &OpcodeInfo,
+    modrm: Option<ModRM>,
+) -> Result<[Option<Operand>; 2], DecodeError> {
+    let width = info.width.unwrap_or(Width::Word);
+    let is_16bit = width != Width::Byte;
+    let modrm = match modrm {
+        Some(modrm) => modrm,
+        None if info.encoding.has_modrm() => decode_modrm_byte(reader.next_byte()?),
+        // Encodings without a ModRM byte never look at it
+        None => decode_modrm_byte(0),
+    };
+    let acc = Operand::Reg(decode_reg(0, is_16bit));
+    let low_reg = Operand::Reg(decode_reg(opcode & 0b111, is_16bit));
+
+    let operands = match info.encoding {
+        OperandEncoding::Implied => [None, None],
+        OperandEncoding::RmReg => {
+            let rm = decode_rm_operand(reader, &modrm, width)?;
+            [
+                Some(rm),
+                Some(Operand::Reg(decode_reg(modrm.reg, is_16bit))),
+            ]
+        }
+        OperandEncoding::RegRm => {
+            let rm = decode_rm_operand(reader, &modrm, width)?;
+            [
+                Some(Operand::Reg(decode_reg(modrm.reg, is_16bit))),
+                Some(rm),
+            ]
+        }
+        OperandEncoding::RmSreg | OperandEncoding::SregRm => {
+            // Only REG values 0-3 name a segment register
+            let segment = SegmentRegister::try_from(modrm.reg)
+                .map_err(|_| reader.error(DecodeErrorKind::InvalidModRM))?;
+            let rm = decode_rm_operand(reader, &modrm, width)?;
+            if info.encoding == OperandEncoding::RmSreg {
+                [Some(rm), Some(Operand::SegReg(segment))]
+            } else {
+                [Some(Operand::SegReg(segment)), Some(rm)]
+            }
+        }
+        OperandEncoding::RegMem => {
+            // Only a memory operand has an address to load
+            if modrm.is_reg() {
+                return Err(reader.error(DecodeErrorKind::InvalidModRM));
+            }
+            let mem = decode_rm_operand(reader, &modrm, width)?;
+            [Some(Operand::Reg(decode_reg(modrm.reg, true))), Some(mem)]
+        }
+        OperandEncoding::RmImm => {
+            let rm = decode_rm_operand(reader, &modrm, width)?;
+            let imm = if is_16bit {
+                Immediate::Word(reader.next_word()?)
+            } else {
+                Immediate::Byte(reader.next_byte()?)
+            };
+            [Some(rm), Some(Operand::Imm(imm))]
+        }
+        OperandEncoding::RmImm8 => {
+            let rm = decode_rm_operand(reader, &modrm, width)?;
+            let imm = Immediate::SignExtended(reader.next_byte()? as i8);
+            [Some(rm), Some(Operand::Imm(imm))]
+        }
+        OperandEncoding::Rm => [Some(decode_rm_operand(reader, &modrm, width)?), None],
+        OperandEncoding::Mem => {
+            if modrm.is_reg() {
+                return Err(reader.error(DecodeErrorKind::InvalidModRM));
+            }
+            [Some(decode_rm_operand(reader, &modrm, width)?), None]
+        }
+        OperandEncoding::RmOne => {
+            let rm = decode_rm_operand(reader, &modrm, width)?;
+            [Some(rm), Some(Operand::Imm(Immediate::Byte(1)))]
+        }
+        OperandEncoding::RmCl => {
+            let rm = decode_rm_operand(reader, &modrm, width)?;
+            [Some(rm), Some(Operand::Reg(Register::CL))]
+        }
+        OperandEncoding::AccImm | OperandEncoding::RegImm => {
+            let dest = if info.encoding == OperandEncoding::AccImm {
+                acc
+            } else {
+                low_reg
+            };
+            let imm = if is_16bit {
+                Immediate::Word(reader.next_word()?)
+            } else {
+                Immediate::Byte(reader.next_byte()?)
+            };
+            [Some(dest), Some(Operand::Imm(imm))]
+        }
+        OperandEncoding::Reg => [Some(low_reg), None],
+        OperandEncoding::AccReg => [Some(acc), Some(low_reg)],
+        OperandEncoding::Sreg => {
+            let segment = SegmentRegister::try_from((opcode >> 3) & 0b11).unwrap();
+            [Some(Operand::SegReg(segment)), None]
+        }
+        OperandEncoding::AccMoffs | OperandEncoding::MoffsAcc => {
+            let offset = reader.next_word()?;
+            let mem = Operand::Mem(Memory {
+                base: None,
+                index: None,
+                disp: Displacement::Word(offset as i16),
+                segment: None,
+                width,
+            });
+            if info.encoding == OperandEncoding::AccMoffs {
+                [Some(acc), Some(mem)]
+            } else {
+                [Some(mem), Some(acc)]
+            }
+        }
+        OperandEncoding::Rel8 => {
+            let disp = reader.next_byte()? as i8 as i16;
+            let target = reader.relative_target(disp);
+            [Some(Operand::RelTarget { target, disp }), None]
+        }
+        OperandEncoding::Rel16 => {
+            let disp = reader.next_word()? as i16;
+            let target = reader.relative_target(disp);
+            [Some(Operand::RelTarget { target, disp }), None]
+        }
+        OperandEncoding::FarPtr => {
+            let offset = reader.next_word()?;
+            let segment = reader.next_word()?;
+            [Some(Operand::FarPtr { segment, offset }), None]
+        }
+        OperandEncoding::Imm8 => [
+            Some(Operand::Imm(Immediate::Byte(reader.next_byte()?))),
+            None,
+        ],
+        OperandEncoding::Imm16 => [
+            Some(Operand::Imm(Immediate::Word(reader.next_word()?))),
+            None,
+        ],
+        OperandEncoding::AccPort => {
+            let port = Operand::Imm(Immediate::Byte(reader.next_byte()?));
+            [Some(acc), Some(port)]
+        }
+        OperandEncoding::PortAcc => {
+            let port = Operand::Imm(Immediate::Byte(reader.next_byte()?));
+            [Some(port), Some(acc)]
+        }
+        OperandEncoding::AccDx => [Some(acc), Some(Operand::Reg(Register::DX))],
+        OperandEncoding::DxAcc => [Some(Operand::Reg(Register::DX)), Some(acc)],
+    };
+    Ok(operands)
+}
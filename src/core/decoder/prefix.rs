@@ -1,54 +1,75 @@
-use crate::core::cpu::Cpu;
+use crate::core::decoder::error::DecodeError;
+use crate::core::decoder::source::Reader;
 use crate::core::instruction::*;
 
-pub fn decode_seg_override(cpu: &mut Cpu, addr: &u32) -> Instruction {
-    let opcode = cpu.read_byte(*addr);
-    cpu.regs.ip += 1;
-    match opcode {
-        0x2E => Instruction::Seg(SegmentOverride {
-            segment: SegmentRegister::CS,
-            length: 1,
-        }),
-        0x3E => Instruction::Seg(SegmentOverride {
-            segment: SegmentRegister::DS,
-            length: 1,
-        }),
-        0x26 => Instruction::Seg(SegmentOverride {
-            segment: SegmentRegister::ES,
-            length: 1,
-        }),
-        0x36 => Instruction::Seg(SegmentOverride {
-            segment: SegmentRegister::SS,
-            length: 1,
-        }),
-        _ => {
-            cpu.regs.ip -= 1;
-            unimplemented!("TODO: Unknown Segment Override: 0x{:2X}", opcode)
-        }
+// Consumes the run of prefix bytes in front of the opcode.
+// When a prefix repeats the last one wins.
+pub fn decode_prefixes(reader: &mut Reader) -> Result<(Prefixes, PrefixBytes), DecodeError> {
+    let mut prefixes = Prefixes::default();
+    let mut bytes = PrefixBytes::default();
+    while apply_prefix(&mut prefixes, reader.peek_byte()?) {
+        bytes.push(reader.next_byte()?);
     }
+    Ok((prefixes, bytes))
 }
 
-pub fn decode_lock(cpu: &mut Cpu, addr: &u32) -> Instruction {
-    let opcode = cpu.read_byte(*addr);
-    match opcode {
-        0xF0 => {
-            cpu.regs.ip += 1;
-            Instruction::Lock(FillerInstruction { length: 1 })
-        }
-        _ => {
-            unimplemented!("TODO: Unknown LOCK opcode: 0x{:2X}", opcode)
+// Folds one prefix byte into prefixes, false if the byte isn't a prefix
+pub fn apply_prefix(prefixes: &mut Prefixes, byte: u8) -> bool {
+    match byte {
+        0x26 | 0x2E | 0x36 | 0x3E => {
+            prefixes.segment = Some(SegmentRegister::try_from((byte >> 3) & 0b11).unwrap())
         }
+        0xF3 => prefixes.rep = Some(RepInstruction::Repz),
+        0xF2 => prefixes.rep = Some(RepInstruction::Repnz),
+        0xF0 => prefixes.lock = Some(LockPrefix::Lock),
+        // F1h is an undocumented alias of LOCK on the 8086
+        0xF1 => prefixes.lock = Some(LockPrefix::LockAlias),
+        _ => return false,
     }
+    true
 }
 
-pub fn decode_rep(cpu: &mut Cpu, addr: &u32) -> Instruction {
-    let opcode = cpu.read_byte(*addr);
-    cpu.regs.ip += 1;
-    match opcode {
-        0xF3 => Instruction::Rep(RepInstruction::Repz),
-        0xF2 => Instruction::Rep(RepInstruction::Repnz),
-        _ => {
-            unimplemented!("TODO: Unknown Rep opcode: 0x{:2X}", opcode)
+#[cfg(test)]
+mod tests {
+    use crate::core::cpu::Cpu;
+    use crate::core::decoder::decode_bytes;
+    use crate::core::decoder::error::DecodeErrorKind;
+    use crate::core::disasm::linear_sweep;
+
+    #[test]
+    fn longest_prefix_run_still_decodes() {
+        let mut bytes = vec![0x26; 254];
+        bytes.push(0x90);
+        let ins = decode_bytes(&bytes, 0x100).unwrap();
+        assert_eq!(ins.length, 255);
+    }
+
+    #[test]
+    fn rejects_instructions_longer_than_a_length_can_hold() {
+        for prefixes in [255, 256, 1000] {
+            let mut bytes = vec![0x26; prefixes];
+            bytes.extend([0x90, 0x90]);
+            let err = decode_bytes(&bytes, 0x100).unwrap_err();
+            assert_eq!(err.kind, DecodeErrorKind::TooLong);
+            assert_eq!(err.bytes.len(), 255);
         }
     }
+
+    #[test]
+    fn sweep_steps_past_an_overlong_prefix_run() {
+        let mut image = vec![0x26; 255];
+        image.push(0x90);
+        let lines = linear_sweep(&image, 0x100);
+        let covered: usize = lines.iter().map(|line| line.bytes.len()).sum();
+        assert_eq!(covered, image.len());
+    }
+
+    #[test]
+    fn memory_full_of_prefixes_is_an_error() {
+        let mut cpu = Cpu::new();
+        cpu.memory.fill(0x2E);
+        let err = cpu.step().unwrap_err();
+        assert_eq!(err.kind, DecodeErrorKind::TooLong);
+        assert_eq!(cpu.regs.ip, 0);
+    }
 }
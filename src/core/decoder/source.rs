@@ -0,0 +1,92 @@
+use crate::core::cpu::Cpu;
+use crate::core::decoder::error::{DecodeError, DecodeErrorKind};
+
+// Anything instructions can be decoded from: emulated memory, a file buffer...
+pub trait ByteSource {
+    // None when addr lies past the end of the source
+    fn byte_at(&self, addr: u32) -> Option<u8>;
+}
+
+impl ByteSource for &[u8] {
+    fn byte_at(&self, addr: u32) -> Option<u8> {
+        self.get(addr as usize).copied()
+    }
+}
+
+impl ByteSource for Vec<u8> {
+    fn byte_at(&self, addr: u32) -> Option<u8> {
+        self.get(addr as usize).copied()
+    }
+}
+
+impl ByteSource for Cpu {
+    fn byte_at(&self, addr: u32) -> Option<u8> {
+        // The 8086 only has 20 address lines, so addresses wrap at 1 MiB
+        Some(self.read_byte(addr & 0xFFFFF))
+    }
+}
+
+// Sequential reader over a ByteSource. It tracks where the instruction
+// started, for errors and lengths, and the IP it runs at, for relative targets.
+pub struct Reader<'a> {
+    source: &'a dyn ByteSource,
+    start: u32,
+    ip: u16,
+    pos: u32,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(source: &'a dyn ByteSource, start: u32, ip: u16) -> Self {
+        Reader {
+            source,
+            start,
+            ip,
+            pos: start,
+        }
+    }
+
+    pub fn peek_byte(&self) -> Result<u8, DecodeError> {
+        self.source
+            .byte_at(self.pos)
+            .ok_or_else(|| self.error(DecodeErrorKind::Truncated))
+    }
+
+    pub fn next_byte(&mut self) -> Result<u8, DecodeError> {
+        // The 8086 takes any number of prefixes, but the length has to fit a u8
+        if self.pos - self.start == u8::MAX as u32 {
+            return Err(self.error(DecodeErrorKind::TooLong));
+        }
+        let byte = self.peek_byte()?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    pub fn next_word(&mut self) -> Result<u16, DecodeError> {
+        let low = self.next_byte()?;
+        let high = self.next_byte()?;
+        Ok(u16::from_le_bytes([low, high]))
+    }
+
+    // Bytes consumed since the first prefix
+    pub fn length(&self) -> u8 {
+        (self.pos - self.start) as u8
+    }
+
+    // Relative displacements count from the end of the instruction, which is
+    // where the reader stands once the displacement itself has been read
+    pub fn relative_target(&self, disp: i16) -> u16 {
+        self.ip
+            .wrapping_add(self.length() as u16)
+            .wrapping_add(disp as u16)
+    }
+
+    pub fn error(&self, kind: DecodeErrorKind) -> DecodeError {
+        DecodeError {
+            address: self.start,
+            bytes: (self.start..self.pos)
+                .filter_map(|addr| self.source.byte_at(addr))
+                .collect(),
+            kind,
+        }
+    }
+}
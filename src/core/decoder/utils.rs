@@ -1,38 +1,70 @@
+use crate::core::decoder::error::DecodeError;
+use crate::core::decoder::source::Reader;
 use crate::core::instruction::*;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModRM {
+    pub mode: u8,
+    pub reg: u8,
+    pub rm: u8,
+}
+
+impl ModRM {
+    // MOD = 11b selects a register instead of memory
+    pub fn is_reg(&self) -> bool {
+        self.mode == 0b11
+    }
+}
+
 pub fn decode_modrm_byte(modrm: u8) -> ModRM {
-    let mod_bits: u8 = (modrm >> 6) & 0b11;
-    let reg_bits: u8 = (modrm >> 3) & 0b111;
-    let rm_bits: u8 = modrm & 0b111;
-
-    // This is the new, more direct and correct way to determine displacement.
-    let disp_mode = if mod_bits == 0b01 {
-        DisplacementMode::BYTE
-    } else if mod_bits == 0b10 {
-        DisplacementMode::WORD
-    } else if mod_bits == 0b00 && rm_bits == 0b110 {
-        DisplacementMode::WORD
-    } else {
-        DisplacementMode::ZERO
-    };
+    ModRM {
+        mode: (modrm >> 6) & 0b11,
+        reg: (modrm >> 3) & 0b111,
+        rm: modrm & 0b111,
+    }
+}
+
+// General purpose register from a REG or R/M field
+pub fn decode_reg(bits: u8, is_16bit: bool) -> Register {
+    Register::try_from(((is_16bit as u8) * 8) + bits).unwrap()
+}
 
-    if mod_bits != 0b11 {
-        // --- Memory Mode ---
-        ModRM {
-            reg_part: reg_bits,
-            displacement_mode: disp_mode,
-            rm_mode: RMMode::Mem(if mod_bits == 0b00 && rm_bits == 0b110 {
-                MemoryMode::DISP16
-            } else {
-                MemoryMode::try_from((mod_bits * 8) + rm_bits).unwrap()
-            }),
-        }
-    } else {
-        // --- Register Mode ---
-        ModRM {
-            reg_part: reg_bits,
-            displacement_mode: DisplacementMode::ZERO, // No displacement in register mode
-            rm_mode: RMMode::Reg(rm_bits),
-        }
+/// Resolves the R/M part of a ModRM byte and reads the displacement that
+/// follows it. A register R/M is 8 or 16 bits depending on the width.
+pub fn decode_rm_operand(
+    reader: &mut Reader,
+    modrm: &ModRM,
+    width: Width,
+) -> Result<Operand, DecodeError> {
+    if modrm.is_reg() {
+        return Ok(Operand::Reg(decode_reg(modrm.rm, width != Width::Byte)));
     }
-}
\ No newline at end of file
+
+    let (base, index) = match modrm.rm {
+        0b000 => (Some(Register::BX), Some(Register::SI)),
+        0b001 => (Some(Register::BX), Some(Register::DI)),
+        0b010 => (Some(Register::BP), Some(Register::SI)),
+        0b011 => (Some(Register::BP), Some(Register::DI)),
+        0b100 => (None, Some(Register::SI)),
+        0b101 => (None, Some(Register::DI)),
+        // MOD = 00b with R/M = 110b is a direct address instead of [BP]
+        0b110 if modrm.mode == 0b00 => (None, None),
+        0b110 => (Some(Register::BP), None),
+        _ => (Some(Register::BX), None),
+    };
+
+    let disp = match modrm.mode {
+        0b01 => Displacement::Byte(reader.next_byte()? as i8),
+        0b10 => Displacement::Word(reader.next_word()? as i16),
+        _ if base.is_none() && index.is_none() => Displacement::Word(reader.next_word()? as i16),
+        _ => Displacement::None,
+    };
+
+    Ok(Operand::Mem(Memory {
+        base,
+        index,
+        disp,
+        segment: None,
+        width,
+    }))
+}
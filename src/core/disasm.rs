@@ -0,0 +1,140 @@
+use crate::core::analysis::Analysis;
+use crate::core::decoder::decode_bytes;
+use crate::core::format::{Symbols, Syntax};
+use crate::core::instruction::Instruction;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Item {
+    Code(Instruction),
+    // Bytes that aren't (known to be) instructions
+    Data,
+}
+
+// One row of a listing: where it starts, the bytes it covers and what they are
+#[derive(Debug, Clone, Copy)]
+pub struct Line<'a> {
+    pub address: u16,
+    pub bytes: &'a [u8],
+    pub item: Item,
+}
+
+// Decodes the image front to back, one instruction after the other, as if
+// it were loaded at `origin`. A byte that can't be decoded is emitted as data
+// and the sweep picks up again at the next one.
+pub fn linear_sweep(image: &[u8], origin: u16) -> Vec<Line<'_>> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    while offset < image.len() {
+        let address = origin.wrapping_add(offset as u16);
+        let (item, length) = match decode_bytes(&image[offset..], address) {
+            Ok(ins) => (Item::Code(ins), ins.length as usize),
+            Err(_) => (Item::Data, 1),
+        };
+        lines.push(Line {
+            address,
+            bytes: &image[offset..offset + length],
+            item,
+        });
+        offset += length;
+    }
+    lines
+}
+
+// Lists the instructions an analysis reached and everything between them as
+// data, at most 8 bytes to a line and split at labels
+pub fn recursive_listing<'a>(image: &'a [u8], analysis: &Analysis) -> Vec<Line<'a>> {
+    let origin = analysis.origin;
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    while offset < image.len() {
+        let address = origin.wrapping_add(offset as u16);
+        if let Some(ins) = analysis.code.get(&address) {
+            let length = ins.length as usize;
+            lines.push(Line {
+                address,
+                bytes: &image[offset..offset + length],
+                item: Item::Code(*ins),
+            });
+            offset += length;
+            continue;
+        }
+
+        let mut end = offset + 1;
+        while end < image.len() && end - offset < 8 {
+            let next = origin.wrapping_add(end as u16);
+            if analysis.code.contains_key(&next) || analysis.labels.contains_key(&next) {
+                break;
+            }
+            end += 1;
+        }
+        lines.push(Line {
+            address,
+            bytes: &image[offset..end],
+            item: Item::Data,
+        });
+        offset = end;
+    }
+    lines
+}
+
+// ndisasm layout: address, hex bytes, then the instruction text. Undocumented
+// opcodes get a comment saying so.
+pub fn format_line(line: &Line, syntax: Syntax, symbols: Option<Symbols>) -> String {
+    let hex: String = line.bytes.iter().map(|b| format!("{:02X}", b)).collect();
+    let mut text = match line.item {
+        Item::Code(ins) => match symbols {
+            Some(symbols) => ins.display(syntax).with_symbols(symbols).to_string(),
+            None => ins.display(syntax).to_string(),
+        },
+        Item::Data => format_data(line.bytes, syntax),
+    };
+    if let Item::Code(ins) = line.item
+        && ins.is_undocumented()
+    {
+        text.push_str(match syntax {
+            Syntax::Intel => " ; undocumented",
+            Syntax::Att => " # undocumented",
+        });
+    }
+    format!("{:08X}  {:<18}{}", line.address, hex, text)
+}
+
+pub fn format_data(bytes: &[u8], syntax: Syntax) -> String {
+    let directive = match syntax {
+        Syntax::Intel => "db",
+        Syntax::Att => ".byte",
+    };
+    let values: Vec<String> = bytes.iter().map(|b| format!("0x{:x}", b)).collect();
+    format!("{} {}", directive, values.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listing(image: &[u8], syntax: Syntax) -> Vec<String> {
+        linear_sweep(image, 0x100)
+            .iter()
+            .map(|line| format_line(line, syntax, None))
+            .collect()
+    }
+
+    #[test]
+    fn annotates_undocumented_opcodes() {
+        // jz via the 64h alias, salc, test al, 0x12 as F6h /1, nop
+        let image = [0x64, 0x05, 0xD6, 0xF6, 0xC8, 0x12, 0x90];
+        assert_eq!(
+            listing(&image, Syntax::Intel),
+            [
+                "00000100  6405              db 0x64, 0x5 ; je 0x0107 ; undocumented",
+                "00000102  D6                salc ; undocumented",
+                "00000103  F6C812            db 0xf6, 0xc8, 0x12 ; test al, 0x12 ; undocumented",
+                "00000106  90                nop",
+            ]
+        );
+        assert_eq!(
+            listing(&image, Syntax::Att)[1],
+            "00000102  D6                salc # undocumented"
+        );
+    }
+}
@@ -0,0 +1,419 @@
+use crate::core::cpu::Flags;
+use crate::core::instruction::*;
+
+use Register::*;
+use SegmentRegister::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+// Where a memory access goes. Operand is the explicit ModRM or moffs
+// operand, the others are implied by the instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Location {
+    Operand(Memory),
+    // SS:SP
+    Stack,
+    // DS:SI of a string operation, or the override segment
+    StringSource,
+    // ES:DI of a string operation
+    StringDestination,
+    // DS:[BX+AL] for XLAT, or the override segment
+    Table,
+    // The 4-byte vector at 0000:4*n
+    InterruptVector,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryAccess {
+    pub location: Location,
+    pub access: Access,
+    pub width: Width,
+}
+
+// What an instruction reads, writes and leaves undefined, explicit and
+// implicit operands alike. Byte registers are reported as such, so MUL BL
+// reads AL and writes AX. REP counts CX as read and written. 8087 stack
+// registers and the FPU's own status aren't tracked.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Effects {
+    pub regs_read: Vec<Register>,
+    pub regs_written: Vec<Register>,
+    pub sregs_read: Vec<SegmentRegister>,
+    pub sregs_written: Vec<SegmentRegister>,
+    pub flags_read: Flags,
+    pub flags_written: Flags,
+    pub flags_undefined: Flags,
+    pub memory: Vec<MemoryAccess>,
+}
+
+impl Effects {
+    #[allow(dead_code)]
+    pub fn reads_memory(&self) -> bool {
+        self.memory.iter().any(|mem| mem.access == Access::Read)
+    }
+
+    #[allow(dead_code)]
+    pub fn writes_memory(&self) -> bool {
+        self.memory.iter().any(|mem| mem.access == Access::Write)
+    }
+
+    fn read(&mut self, regs: &[Register]) {
+        for reg in regs {
+            if !self.regs_read.contains(reg) {
+                self.regs_read.push(*reg);
+            }
+        }
+    }
+
+    fn write(&mut self, regs: &[Register]) {
+        for reg in regs {
+            if !self.regs_written.contains(reg) {
+                self.regs_written.push(*reg);
+            }
+        }
+    }
+
+    fn read_sreg(&mut self, sreg: SegmentRegister) {
+        if !self.sregs_read.contains(&sreg) {
+            self.sregs_read.push(sreg);
+        }
+    }
+
+    fn write_sreg(&mut self, sreg: SegmentRegister) {
+        if !self.sregs_written.contains(&sreg) {
+            self.sregs_written.push(sreg);
+        }
+    }
+
+    fn access(&mut self, location: Location, access: Access, width: Width) {
+        self.memory.push(MemoryAccess {
+            location,
+            access,
+            width,
+        });
+    }
+
+    // PUSH/POP style access of `words` words at SS:SP
+    fn stack(&mut self, access: Access, words: usize) {
+        self.read(&[SP]);
+        self.write(&[SP]);
+        self.read_sreg(SS);
+        for _ in 0..words {
+            self.access(Location::Stack, access, Width::Word);
+        }
+    }
+
+    fn string_source(&mut self, segment: SegmentRegister, width: Width) {
+        self.read(&[SI]);
+        self.write(&[SI]);
+        self.read_sreg(segment);
+        self.access(Location::StringSource, Access::Read, width);
+    }
+
+    fn string_destination(&mut self, access: Access, width: Width) {
+        self.read(&[DI]);
+        self.write(&[DI]);
+        self.read_sreg(ES);
+        self.access(Location::StringDestination, access, width);
+    }
+
+    fn operand(&mut self, operand: &Operand, access: OperandAccess, effective_address: bool) {
+        let reads = matches!(access, OperandAccess::Read | OperandAccess::ReadWrite);
+        let writes = matches!(access, OperandAccess::Write | OperandAccess::ReadWrite);
+        match operand {
+            Operand::Reg(reg) => {
+                if reads {
+                    self.read(&[*reg]);
+                }
+                if writes {
+                    self.write(&[*reg]);
+                }
+            }
+            Operand::SegReg(sreg) => {
+                if reads {
+                    self.read_sreg(*sreg);
+                }
+                if writes {
+                    self.write_sreg(*sreg);
+                }
+            }
+            Operand::Mem(mem) => {
+                for reg in [mem.base, mem.index].into_iter().flatten() {
+                    self.read(&[reg]);
+                }
+                // LEA only computes the address
+                if !effective_address {
+                    self.read_sreg(mem.effective_segment());
+                    if reads {
+                        self.access(Location::Operand(*mem), Access::Read, mem.width);
+                    }
+                    if writes {
+                        self.access(Location::Operand(*mem), Access::Write, mem.width);
+                    }
+                }
+            }
+            Operand::Imm(_)
+            | Operand::RelTarget { .. }
+            | Operand::FarPtr { .. }
+            | Operand::St(_) => {}
+        }
+    }
+}
+
+impl Instruction {
+    // For the executor and analysis passes, nothing calls it yet
+    #[allow(dead_code)]
+    pub fn effects(&self) -> Effects {
+        use Mnemonic::*;
+        let mut effects = Effects::default();
+        // XCHG AX, AX does nothing at all
+        if self.is_nop() {
+            return effects;
+        }
+        if let Some(info) = self.info() {
+            effects.flags_read = info.flags.read;
+            effects.flags_written = info.flags.written;
+            effects.flags_undefined = info.flags.undefined;
+        }
+
+        let (dest, src) = self.operand_access();
+        let lea = self.mnemonic == Lea;
+        if let Some(operand) = &self.operands[0] {
+            effects.operand(operand, dest, lea);
+        }
+        if let Some(operand) = &self.operands[1] {
+            effects.operand(operand, src, lea);
+        }
+
+        let word = self.is_word();
+        let acc = if word { AX } else { AL };
+        let far = matches!(self.operands[0], Some(Operand::FarPtr { .. }))
+            || matches!(self.operands[0], Some(Operand::Mem(mem)) if mem.width == Width::Dword);
+        let width = if word { Width::Word } else { Width::Byte };
+        let source_segment = self.prefixes.segment.unwrap_or(DS);
+
+        match self.mnemonic {
+            Mul | Imul if word => {
+                effects.read(&[AX]);
+                effects.write(&[AX, DX]);
+            }
+            Mul | Imul => {
+                effects.read(&[AL]);
+                effects.write(&[AX]);
+            }
+            Div | Idiv if word => {
+                effects.read(&[AX, DX]);
+                effects.write(&[AX, DX]);
+            }
+            Div | Idiv => {
+                effects.read(&[AX]);
+                effects.write(&[AX]);
+            }
+            Cbw => {
+                effects.read(&[AL]);
+                effects.write(&[AH]);
+            }
+            Cwd => {
+                effects.read(&[AX]);
+                effects.write(&[DX]);
+            }
+            Aaa | Aas | Aad => {
+                effects.read(&[AX]);
+                effects.write(&[AX]);
+            }
+            Aam => {
+                effects.read(&[AL]);
+                effects.write(&[AX]);
+            }
+            Daa | Das => {
+                effects.read(&[AL]);
+                effects.write(&[AL]);
+            }
+            Salc => effects.write(&[AL]),
+            Lahf => effects.write(&[AH]),
+            Sahf => effects.read(&[AH]),
+            Xlatb => {
+                effects.read(&[AL, BX]);
+                effects.write(&[AL]);
+                effects.read_sreg(source_segment);
+                effects.access(Location::Table, Access::Read, Width::Byte);
+            }
+            Lds => effects.write_sreg(DS),
+            Les => effects.write_sreg(ES),
+
+            Push | Pushf => effects.stack(Access::Write, 1),
+            Pop | Popf => effects.stack(Access::Read, 1),
+            Call if far => {
+                effects.stack(Access::Write, 2);
+                effects.read_sreg(CS);
+                effects.write_sreg(CS);
+            }
+            Call => effects.stack(Access::Write, 1),
+            Jmp if far => effects.write_sreg(CS),
+            Ret => effects.stack(Access::Read, 1),
+            Retf => {
+                effects.stack(Access::Read, 2);
+                effects.write_sreg(CS);
+            }
+            Iret => {
+                effects.stack(Access::Read, 3);
+                effects.write_sreg(CS);
+            }
+            // FLAGS, CS and IP go on the stack, CS:IP come from the vector
+            Int | Int3 | Into => {
+                effects.stack(Access::Write, 3);
+                effects.read_sreg(CS);
+                effects.write_sreg(CS);
+                effects.access(Location::InterruptVector, Access::Read, Width::Dword);
+            }
+            Jcxz => effects.read(&[CX]),
+            Loop | Loopz | Loopnz => {
+                effects.read(&[CX]);
+                effects.write(&[CX]);
+            }
+
+            Movsb | Movsw => {
+                effects.string_source(source_segment, width);
+                effects.string_destination(Access::Write, width);
+            }
+            Cmpsb | Cmpsw => {
+                effects.string_source(source_segment, width);
+                effects.string_destination(Access::Read, width);
+            }
+            Lodsb | Lodsw => {
+                effects.string_source(source_segment, width);
+                effects.write(&[acc]);
+            }
+            Stosb | Stosw => {
+                effects.read(&[acc]);
+                effects.string_destination(Access::Write, width);
+            }
+            Scasb | Scasw => {
+                effects.read(&[acc]);
+                effects.string_destination(Access::Read, width);
+            }
+            _ => {}
+        }
+
+        if self.prefixes.rep.is_some() && self.is_string() {
+            effects.read(&[CX]);
+            effects.write(&[CX]);
+        }
+        effects
+    }
+
+    fn is_string(&self) -> bool {
+        use Mnemonic::*;
+        matches!(
+            self.mnemonic,
+            Movsb | Movsw | Cmpsb | Cmpsw | Stosb | Stosw | Lodsb | Lodsw | Scasb | Scasw
+        )
+    }
+
+    // Operand size of the instruction, from the mnemonic for string
+    // operations and from the operands for everything else
+    fn is_word(&self) -> bool {
+        use Mnemonic::*;
+        match self.mnemonic {
+            Movsw | Cmpsw | Stosw | Lodsw | Scasw => true,
+            Movsb | Cmpsb | Stosb | Lodsb | Scasb => false,
+            _ => self.operands().any(|operand| match operand {
+                Operand::Reg(reg) => reg.is_16bit(),
+                Operand::Mem(mem) => mem.width == Width::Word,
+                _ => false,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::decoder::decode_bytes;
+
+    fn effects(bytes: &[u8]) -> Effects {
+        decode_bytes(bytes, 0x100).unwrap().effects()
+    }
+
+    #[test]
+    fn multiply_and_divide_use_ax_and_dx() {
+        // mul bx
+        let mul = effects(&[0xF7, 0xE3]);
+        assert_eq!(mul.regs_read, [BX, AX]);
+        assert_eq!(mul.regs_written, [AX, DX]);
+        // mul bl
+        let mul = effects(&[0xF6, 0xE3]);
+        assert_eq!(mul.regs_read, [BL, AL]);
+        assert_eq!(mul.regs_written, [AX]);
+        // div bx
+        let div = effects(&[0xF7, 0xF3]);
+        assert_eq!(div.regs_read, [BX, AX, DX]);
+        assert_eq!(div.regs_written, [AX, DX]);
+        // div bl
+        let div = effects(&[0xF6, 0xF3]);
+        assert_eq!(div.regs_read, [BL, AX]);
+        assert_eq!(div.regs_written, [AX]);
+    }
+
+    #[test]
+    fn string_operations_use_cx_only_with_rep() {
+        // rep movsw
+        let movs = effects(&[0xF3, 0xA5]);
+        assert_eq!(movs.regs_read, [SI, DI, CX]);
+        assert_eq!(movs.regs_written, [SI, DI, CX]);
+        assert_eq!(movs.sregs_read, [DS, ES]);
+        assert_eq!(
+            movs.memory,
+            [
+                MemoryAccess {
+                    location: Location::StringSource,
+                    access: Access::Read,
+                    width: Width::Word,
+                },
+                MemoryAccess {
+                    location: Location::StringDestination,
+                    access: Access::Write,
+                    width: Width::Word,
+                },
+            ]
+        );
+        // movsw
+        let movs = effects(&[0xA5]);
+        assert!(!movs.regs_read.contains(&CX));
+        assert!(!movs.regs_written.contains(&CX));
+        // es movsb reads from ES:SI
+        assert_eq!(effects(&[0x26, 0xA4]).sregs_read, [ES]);
+        // repe scasb
+        let scas = effects(&[0xF3, 0xAE]);
+        assert_eq!(scas.regs_read, [AL, DI, CX]);
+        assert_eq!(scas.regs_written, [DI, CX]);
+        assert!(scas.reads_memory() && !scas.writes_memory());
+    }
+
+    #[test]
+    fn bp_addresses_the_stack_segment() {
+        // mov ax, [bp+2]
+        let mov = effects(&[0x8B, 0x46, 0x02]);
+        assert_eq!(mov.regs_read, [BP]);
+        assert_eq!(mov.sregs_read, [SS]);
+        assert!(mov.reads_memory());
+        // ds mov ax, [bp+2]
+        assert_eq!(effects(&[0x3E, 0x8B, 0x46, 0x02]).sregs_read, [DS]);
+        // mov ax, [bx+2]
+        assert_eq!(effects(&[0x8B, 0x47, 0x02]).sregs_read, [DS]);
+    }
+
+    #[test]
+    fn lea_does_not_touch_memory() {
+        // lea ax, [bp+2]
+        let lea = effects(&[0x8D, 0x46, 0x02]);
+        assert_eq!(lea.regs_read, [BP]);
+        assert_eq!(lea.regs_written, [AX]);
+        assert!(lea.sregs_read.is_empty());
+        assert!(lea.memory.is_empty());
+    }
+}
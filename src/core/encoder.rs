@@ -0,0 +1,722 @@
+use std::fmt;
+
+use crate::core::decoder::{Operation, apply_prefix, memory_operation, register_operation};
+use crate::core::instruction::*;
+use crate::core::opcodes::{OPCODES, Opcode, OpcodeInfo, OperandEncoding};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodeError {
+    // No opcode takes this mnemonic with these operands
+    NoEncoding,
+    // A relative target the displacement can't reach
+    OutOfRange,
+    // The prefix run was longer than PrefixBytes keeps, so its exact bytes
+    // are gone
+    LostPrefixes,
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::NoEncoding => write!(f, "no encoding for these operands"),
+            EncodeError::OutOfRange => write!(f, "relative target out of range"),
+            EncodeError::LostPrefixes => write!(f, "prefix run too long to reproduce"),
+        }
+    }
+}
+
+impl std::error::Error for EncodeError {}
+
+// How much of the decoded instruction an encoding has to keep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Keep {
+    // Only the operands, displacements are as small as they go
+    Operands,
+    // Also the width of displacements, word immediates and jumps
+    Sizes,
+    // Also the opcode, the prefix bytes and any ignored REG value
+    Bytes,
+}
+
+// Encodes the instruction as if it were placed at offset ip, picking the
+// shortest form with the smallest displacements. Ties go to the lowest
+// opcode, so MOV AX, BX comes out as 89h. Undocumented aliases such as
+// 60h-6Fh or C0h are never picked. XCHG and TEST don't care about operand
+// order, so XCHG CX, AX can still use the one-byte 91h. XCHG BX, CX puts BX
+// in REG like NASM does, TEST BX, CX puts it in R/M.
+#[allow(dead_code)] // no assembler front end yet
+pub fn encode(ins: &Instruction, ip: u16) -> Result<Vec<u8>, EncodeError> {
+    encode_shortest(ins, ip, Keep::Operands)
+}
+
+// What encode picks when displacements keep their decoded width, a word
+// immediate stays a word and a short or near jump stays one, all of which
+// NASM source can spell out. Where this differs from original_bytes the
+// decoded form can't be had from NASM without db.
+pub fn encode_sized(ins: &Instruction) -> Result<Vec<u8>, EncodeError> {
+    encode_shortest(ins, decoded_ip(ins), Keep::Sizes)
+}
+
+fn encode_shortest(ins: &Instruction, ip: u16, keep: Keep) -> Result<Vec<u8>, EncodeError> {
+    let mut forms = vec![*ins];
+    let [dest, src] = ins.operands;
+    let swapped = Instruction {
+        operands: [src, dest],
+        ..*ins
+    };
+    match ins.mnemonic {
+        Mnemonic::Xchg => forms.insert(0, swapped),
+        Mnemonic::Test => forms.push(swapped),
+        _ => {}
+    }
+
+    let mut best: Option<Vec<u8>> = None;
+    let mut error = EncodeError::NoEncoding;
+    let candidates =
+        (0..=0xFF).filter(|&opcode| !is_alias(opcode) && has_mnemonic(opcode, ins.mnemonic));
+    for opcode in candidates {
+        for form in &forms {
+            match encode_opcode(form, ip, opcode, keep) {
+                Ok(bytes) if best.as_ref().is_none_or(|best| bytes.len() < best.len()) => {
+                    best = Some(bytes)
+                }
+                Ok(_) => {}
+                Err(EncodeError::OutOfRange) => error = EncodeError::OutOfRange,
+                Err(_) => {}
+            }
+        }
+    }
+    best.ok_or(error)
+}
+
+// Encodes the instruction with the given opcode, e.g. 8Bh rather than 89h for
+// MOV AX, BX. Displacements keep the size they were decoded with, and the
+// prefix bytes and ModRM REG field the decoder recorded are reused where they
+// still fit, so encode_as(ins, ip, ins.opcode) gives back the bytes the
+// instruction came from. That includes prefixes out of order or repeated and
+// the aliases that ignore REG, like F6h /1 or C6h /3. A prefix run longer than
+// PrefixBytes::CAPACITY can't be reproduced and is an error.
+pub fn encode_as(ins: &Instruction, ip: u16, opcode: u8) -> Result<Vec<u8>, EncodeError> {
+    encode_opcode(ins, ip, opcode, Keep::Bytes)
+}
+
+// The bytes a decoded instruction came from. Relative targets are encoded
+// from their displacement, so unlike encode_as this needs no IP.
+pub fn original_bytes(ins: &Instruction) -> Result<Vec<u8>, EncodeError> {
+    encode_as(ins, decoded_ip(ins), ins.opcode)
+}
+
+// Where a decoded instruction sat, as far as its encoding depends on it
+fn decoded_ip(ins: &Instruction) -> u16 {
+    match ins.operands[0] {
+        Some(Operand::RelTarget { target, disp }) => target
+            .wrapping_sub(disp as u16)
+            .wrapping_sub(ins.length as u16),
+        _ => 0,
+    }
+}
+
+// Opcodes that only duplicate another one
+fn is_alias(opcode: u8) -> bool {
+    matches!(opcode, 0x60..=0x6F | 0x82 | 0xC0 | 0xC1 | 0xC8 | 0xC9)
+}
+
+// Whether the table entry can stand for the mnemonic at all. ESC opcodes
+// are sorted out by encode_esc.
+fn has_mnemonic(opcode: u8, mnemonic: Mnemonic) -> bool {
+    match &OPCODES[opcode as usize] {
+        Opcode::Op(info) => info.mnemonic == mnemonic,
+        Opcode::Group(group) => group.iter().flatten().any(|info| info.mnemonic == mnemonic),
+        Opcode::Esc => true,
+        Opcode::Prefix => false,
+    }
+}
+
+fn encode_opcode(
+    ins: &Instruction,
+    ip: u16,
+    opcode: u8,
+    keep: Keep,
+) -> Result<Vec<u8>, EncodeError> {
+    let mut out = match keep {
+        Keep::Bytes => original_prefixes(ins)?,
+        Keep::Operands | Keep::Sizes => canonical_prefixes(ins),
+    };
+    out.push(opcode);
+    // The REG field as decoded, where it isn't an operand and the 8086 lets
+    // more than one value through
+    let reg = match ins.modrm {
+        Some(modrm) if keep == Keep::Bytes && opcode == ins.opcode => Some((modrm >> 3) & 0b111),
+        _ => None,
+    };
+    match &OPCODES[opcode as usize] {
+        Opcode::Op(info) if info.mnemonic == ins.mnemonic => {
+            encode_operands(ins, ip, opcode, info, reg.unwrap_or(0), keep, out)
+        }
+        Opcode::Op(_) | Opcode::Prefix => Err(EncodeError::NoEncoding),
+        // The REG value decoded, else the first that fits, /0 for the TEST
+        // that /1 repeats
+        Opcode::Group(group) => reg
+            .into_iter()
+            .chain(0..8)
+            .filter_map(|reg| Some((reg, group[reg as usize].as_ref()?)))
+            .filter(|(_, info)| info.mnemonic == ins.mnemonic)
+            .map(|(reg, info)| encode_operands(ins, ip, opcode, info, reg, keep, out.clone()))
+            .find(Result::is_ok)
+            .unwrap_or(Err(EncodeError::NoEncoding)),
+        Opcode::Esc => encode_esc(ins, opcode, keep == Keep::Operands, out),
+    }
+}
+
+// The prefixes an instruction needs, with the override on a memory operand
+// counted as a segment prefix
+fn wanted_prefixes(ins: &Instruction) -> Prefixes {
+    let memory_segment = ins.operands().find_map(|operand| match operand {
+        Operand::Mem(mem) => mem.segment,
+        _ => None,
+    });
+    Prefixes {
+        segment: ins.prefixes.segment.or(memory_segment),
+        ..ins.prefixes
+    }
+}
+
+// One of each kind, in the order NASM writes them: REP, LOCK, segment
+fn canonical_prefixes(ins: &Instruction) -> Vec<u8> {
+    let prefixes = wanted_prefixes(ins);
+    let mut out = Vec::new();
+    match prefixes.rep {
+        Some(RepInstruction::Repz) => out.push(0xF3),
+        Some(RepInstruction::Repnz) => out.push(0xF2),
+        None => {}
+    }
+    match prefixes.lock {
+        Some(LockPrefix::Lock) => out.push(0xF0),
+        Some(LockPrefix::LockAlias) => out.push(0xF1),
+        None => {}
+    }
+    if let Some(segment) = prefixes.segment {
+        out.push(0x26 | ((segment as u8) << 3));
+    }
+    out
+}
+
+// The prefix bytes the instruction was decoded with, as long as they still
+// amount to its prefixes. Canonical ones for anything built by hand or changed
+// since.
+fn original_prefixes(ins: &Instruction) -> Result<Vec<u8>, EncodeError> {
+    if ins.prefix_bytes.is_empty() {
+        return Ok(canonical_prefixes(ins));
+    }
+    let Some(bytes) = ins.prefix_bytes.bytes() else {
+        return Err(EncodeError::LostPrefixes);
+    };
+    let mut prefixes = Prefixes::default();
+    for &byte in bytes {
+        apply_prefix(&mut prefixes, byte);
+    }
+    if prefixes == wanted_prefixes(ins) {
+        Ok(bytes.to_vec())
+    } else {
+        Ok(canonical_prefixes(ins))
+    }
+}
+
+// Appends whatever follows the opcode according to its table entry. `reg` is
+// the REG field for encodings that don't put an operand there.
+fn encode_operands(
+    ins: &Instruction,
+    ip: u16,
+    opcode: u8,
+    info: &OpcodeInfo,
+    reg: u8,
+    keep: Keep,
+    mut out: Vec<u8>,
+) -> Result<Vec<u8>, EncodeError> {
+    use OperandEncoding as E;
+    let shortest = keep == Keep::Operands;
+    // Keeping sizes, a jump keeps its width and a word immediate stays out
+    // of the sign-extended 83h form
+    let sized = keep == Keep::Sizes;
+    let decoded = match &OPCODES[ins.opcode as usize] {
+        Opcode::Op(info) => Some(info.encoding),
+        _ => None,
+    };
+    let width = info.width.unwrap_or(Width::Word);
+    let is_16bit = width != Width::Byte;
+    let acc = if is_16bit { Register::AX } else { Register::AL };
+    let low_reg = |r: Register| r.is_16bit() == is_16bit && r as u8 & 0b111 == opcode & 0b111;
+
+    match (info.encoding, ins.operands) {
+        (E::Implied, [None, None]) => {}
+        (E::RmReg, [Some(rm), Some(Operand::Reg(r))])
+        | (E::RegRm, [Some(Operand::Reg(r)), Some(rm)])
+            if r.is_16bit() == is_16bit =>
+        {
+            push_modrm(&mut out, r as u8 & 0b111, &rm, width, shortest)?
+        }
+        (E::RmSreg, [Some(rm), Some(Operand::SegReg(s))])
+        | (E::SregRm, [Some(Operand::SegReg(s)), Some(rm)]) => {
+            push_modrm(&mut out, s as u8, &rm, width, shortest)?
+        }
+        (E::RegMem, [Some(Operand::Reg(r)), Some(mem @ Operand::Mem(_))]) if r.is_16bit() => {
+            push_modrm(&mut out, r as u8 & 0b111, &mem, width, shortest)?
+        }
+        (E::RmImm, [Some(rm), Some(Operand::Imm(imm))]) => {
+            push_modrm(&mut out, reg, &rm, width, shortest)?;
+            push_imm(&mut out, imm, width)?;
+        }
+        (E::RmImm8, [Some(rm), Some(Operand::Imm(imm))])
+            if !(sized && matches!(imm, Immediate::Word(_))) =>
+        {
+            let value = i8::try_from(imm_value(imm) as i16).or(Err(EncodeError::NoEncoding))?;
+            push_modrm(&mut out, reg, &rm, width, shortest)?;
+            out.push(value as u8);
+        }
+        (E::Rm, [Some(rm), None]) | (E::Mem, [Some(rm @ Operand::Mem(_)), None]) => {
+            push_modrm(&mut out, reg, &rm, width, shortest)?
+        }
+        (E::RmOne, [Some(rm), Some(Operand::Imm(imm))]) if imm_value(imm) == 1 => {
+            push_modrm(&mut out, reg, &rm, width, shortest)?
+        }
+        (E::RmCl, [Some(rm), Some(Operand::Reg(Register::CL))]) => {
+            push_modrm(&mut out, reg, &rm, width, shortest)?
+        }
+        (E::AccImm, [Some(Operand::Reg(r)), Some(Operand::Imm(imm))]) if r == acc => {
+            push_imm(&mut out, imm, width)?
+        }
+        (E::RegImm, [Some(Operand::Reg(r)), Some(Operand::Imm(imm))]) if low_reg(r) => {
+            push_imm(&mut out, imm, width)?
+        }
+        (E::Reg, [Some(Operand::Reg(r)), None]) if low_reg(r) => {}
+        (E::AccReg, [Some(Operand::Reg(Register::AX)), Some(Operand::Reg(r))]) if low_reg(r) => {}
+        (E::Sreg, [Some(Operand::SegReg(s)), None]) if s as u8 == (opcode >> 3) & 0b11 => {}
+        (E::AccMoffs, [Some(Operand::Reg(r)), Some(Operand::Mem(mem))])
+        | (E::MoffsAcc, [Some(Operand::Mem(mem)), Some(Operand::Reg(r))])
+            if r == acc && is_direct(&mem) && mem.width == width =>
+        {
+            out.extend(mem.disp.value().to_le_bytes())
+        }
+        (E::Rel8, [Some(Operand::RelTarget { target, .. }), None])
+            if !(sized && decoded == Some(E::Rel16)) =>
+        {
+            let next = ip.wrapping_add(out.len() as u16 + 1);
+            let disp = target.wrapping_sub(next) as i16;
+            let disp = i8::try_from(disp).or(Err(EncodeError::OutOfRange))?;
+            out.push(disp as u8);
+        }
+        (E::Rel16, [Some(Operand::RelTarget { target, .. }), None])
+            if !(sized && decoded == Some(E::Rel8)) =>
+        {
+            let next = ip.wrapping_add(out.len() as u16 + 2);
+            out.extend(target.wrapping_sub(next).to_le_bytes());
+        }
+        (E::FarPtr, [Some(Operand::FarPtr { segment, offset }), None]) => {
+            out.extend(offset.to_le_bytes());
+            out.extend(segment.to_le_bytes());
+        }
+        (E::Imm8, [Some(Operand::Imm(imm)), None]) => push_imm(&mut out, imm, Width::Byte)?,
+        (E::Imm16, [Some(Operand::Imm(imm)), None]) => push_imm(&mut out, imm, Width::Word)?,
+        (E::AccPort, [Some(Operand::Reg(r)), Some(Operand::Imm(port))])
+        | (E::PortAcc, [Some(Operand::Imm(port)), Some(Operand::Reg(r))])
+            if r == acc =>
+        {
+            push_imm(&mut out, port, Width::Byte)?
+        }
+        (E::AccDx, [Some(Operand::Reg(r)), Some(Operand::Reg(Register::DX))])
+        | (E::DxAcc, [Some(Operand::Reg(Register::DX)), Some(Operand::Reg(r))])
+            if r == acc => {}
+        _ => return Err(EncodeError::NoEncoding),
+    }
+    Ok(out)
+}
+
+// 8087 instructions: the REG field (and R/M for register forms) picks the
+// operation, so search for the combination that decodes to this one
+fn encode_esc(
+    ins: &Instruction,
+    opcode: u8,
+    shortest: bool,
+    mut out: Vec<u8>,
+) -> Result<Vec<u8>, EncodeError> {
+    let operation = Operation {
+        mnemonic: ins.mnemonic,
+        operands: ins.operands,
+    };
+    match operation.operands {
+        // Raw ESC n: the low opcode bits and REG hold n
+        [Some(Operand::Imm(Immediate::Byte(n))), Some(rm)]
+            if ins.mnemonic == Mnemonic::Esc && n < 64 && 0xD8 | (n >> 3) == opcode =>
+        {
+            let reg = n & 0b111;
+            match rm {
+                Operand::St(sti) if sti < 8 => out.push(0xC0 | (reg << 3) | sti),
+                Operand::Mem(mem) => push_memory(&mut out, reg, &mem, shortest)?,
+                _ => return Err(EncodeError::NoEncoding),
+            }
+        }
+        [Some(Operand::Mem(mem)), None] => {
+            let reg = (0..8)
+                .find(|&reg| memory_operation(opcode, reg) == Some((ins.mnemonic, mem.width)))
+                .ok_or(EncodeError::NoEncoding)?;
+            push_memory(&mut out, reg, &mem, shortest)?;
+        }
+        // Register forms only ever name ST(i)
+        operands
+            if operands
+                .iter()
+                .flatten()
+                .all(|op| matches!(op, Operand::St(_))) =>
+        {
+            let (reg, sti) = (0..8)
+                .flat_map(|reg| (0..8).map(move |sti| (reg, sti)))
+                .find(|&(reg, sti)| register_operation(opcode, reg, sti) == Some(operation))
+                .ok_or(EncodeError::NoEncoding)?;
+            out.push(0xC0 | (reg << 3) | sti);
+        }
+        _ => return Err(EncodeError::NoEncoding),
+    }
+    Ok(out)
+}
+
+// ModRM byte with `reg` in the REG field, plus displacement, for a register
+// or memory R/M operand of the given width
+fn push_modrm(
+    out: &mut Vec<u8>,
+    reg: u8,
+    rm: &Operand,
+    width: Width,
+    shortest: bool,
+) -> Result<(), EncodeError> {
+    match rm {
+        Operand::Reg(r) if r.is_16bit() == (width != Width::Byte) => {
+            out.push(0xC0 | (reg << 3) | (*r as u8 & 0b111));
+            Ok(())
+        }
+        Operand::Mem(mem) if mem.width == width => push_memory(out, reg, mem, shortest),
+        _ => Err(EncodeError::NoEncoding),
+    }
+}
+
+fn push_memory(
+    out: &mut Vec<u8>,
+    reg: u8,
+    mem: &Memory,
+    shortest: bool,
+) -> Result<(), EncodeError> {
+    use Register::*;
+    let rm = match (mem.base, mem.index) {
+        (Some(BX), Some(SI)) => 0b000,
+        (Some(BX), Some(DI)) => 0b001,
+        (Some(BP), Some(SI)) => 0b010,
+        (Some(BP), Some(DI)) => 0b011,
+        (None, Some(SI)) => 0b100,
+        (None, Some(DI)) => 0b101,
+        (Some(BP), None) => 0b110,
+        (Some(BX), None) => 0b111,
+        // MOD = 00b with R/M = 110b, always a 16-bit address
+        (None, None) => {
+            out.push((reg << 3) | 0b110);
+            out.extend(mem.disp.value().to_le_bytes());
+            return Ok(());
+        }
+        _ => return Err(EncodeError::NoEncoding),
+    };
+
+    let disp = if shortest {
+        match mem.disp.value() {
+            0 => Displacement::None,
+            disp => match i8::try_from(disp) {
+                Ok(disp) => Displacement::Byte(disp),
+                Err(_) => Displacement::Word(disp),
+            },
+        }
+    } else {
+        mem.disp
+    };
+    // [BP] has no MOD = 00b form, it takes a zero byte displacement
+    let disp = match disp {
+        Displacement::None if rm == 0b110 => Displacement::Byte(0),
+        disp => disp,
+    };
+    match disp {
+        Displacement::None => out.push((reg << 3) | rm),
+        Displacement::Byte(disp) => {
+            out.push(0b0100_0000 | (reg << 3) | rm);
+            out.push(disp as u8);
+        }
+        Displacement::Word(disp) => {
+            out.push(0b1000_0000 | (reg << 3) | rm);
+            out.extend(disp.to_le_bytes());
+        }
+    }
+    Ok(())
+}
+
+fn is_direct(mem: &Memory) -> bool {
+    mem.base.is_none() && mem.index.is_none()
+}
+
+// The 16-bit value an immediate stands for
+fn imm_value(imm: Immediate) -> u16 {
+    match imm {
+        Immediate::Byte(value) => value as u16,
+        Immediate::Word(value) => value,
+        Immediate::SignExtended(value) => value as i16 as u16,
+    }
+}
+
+// An 8-bit field takes anything that fits as either signed or unsigned
+fn push_imm(out: &mut Vec<u8>, imm: Immediate, width: Width) -> Result<(), EncodeError> {
+    let value = imm_value(imm);
+    if width == Width::Byte {
+        if value > 0xFF && value < 0xFF80 {
+            return Err(EncodeError::NoEncoding);
+        }
+        out.push(value as u8);
+    } else {
+        out.extend(value.to_le_bytes());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::core::decoder::decode_bytes;
+
+    const IP: u16 = 0x100;
+
+    // Bytes after the opcode and ModRM, enough for the longest displacement
+    // and immediate
+    const TAILS: [[u8; 4]; 4] = [
+        [0x00; 4],
+        [0x12, 0x34, 0x56, 0x78],
+        [0xFF; 4],
+        [0x80, 0x7F, 0x80, 0x7F],
+    ];
+
+    // Every instruction that decodes from the prefixes, an opcode, a ModRM
+    // byte and one of the tails, once each
+    fn every_instruction(prefixes: &[u8], tails: &[[u8; 4]]) -> Vec<(Vec<u8>, Instruction)> {
+        let mut seen = HashSet::new();
+        let mut found = Vec::new();
+        for opcode in 0..=0xFFu8 {
+            if matches!(OPCODES[opcode as usize], Opcode::Prefix) {
+                continue;
+            }
+            for modrm in 0..=0xFFu8 {
+                for tail in tails {
+                    let mut bytes = prefixes.to_vec();
+                    bytes.extend([opcode, modrm]);
+                    bytes.extend(tail);
+                    let Ok(ins) = decode_bytes(&bytes, IP) else {
+                        continue;
+                    };
+                    bytes.truncate(ins.length as usize);
+                    if seen.insert(bytes.clone()) {
+                        found.push((bytes, ins));
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    fn check_round_trip(prefixes: &[u8], tails: &[[u8; 4]]) {
+        for (bytes, ins) in every_instruction(prefixes, tails) {
+            assert_eq!(
+                encode_as(&ins, IP, ins.opcode),
+                Ok(bytes.clone()),
+                "{} ({:02X?})",
+                ins,
+                bytes
+            );
+        }
+    }
+
+    // Operands with every displacement and immediate written as a word and
+    // branches reduced to their target, so the smallest encoding compares
+    // equal to the original
+    fn normalized(ins: &Instruction) -> [Option<Operand>; 2] {
+        ins.operands.map(|operand| match operand {
+            Some(Operand::Mem(mem)) => Some(Operand::Mem(Memory {
+                disp: Displacement::Word(mem.disp.value()),
+                ..mem
+            })),
+            Some(Operand::Imm(imm)) => Some(Operand::Imm(Immediate::Word(imm_value(imm)))),
+            Some(Operand::RelTarget { target, .. }) => Some(Operand::RelTarget { target, disp: 0 }),
+            operand => operand,
+        })
+    }
+
+    fn encode_one(bytes: &[u8]) -> Vec<u8> {
+        encode(&decode_bytes(bytes, IP).unwrap(), IP).unwrap()
+    }
+
+    #[test]
+    fn round_trips_every_opcode() {
+        check_round_trip(&[], &TAILS);
+    }
+
+    #[test]
+    fn round_trips_with_prefixes() {
+        for prefixes in [
+            &[0x26][..],
+            &[0x2E],
+            &[0x36],
+            &[0x3E],
+            &[0xF3],
+            &[0xF2],
+            &[0xF0],
+            &[0xF1],
+            &[0xF0, 0xF3, 0x26],
+            &[0x26, 0xF3],
+            &[0x3E, 0x3E],
+            &[0xF2, 0xF3, 0x2E, 0x26],
+        ] {
+            check_round_trip(prefixes, &TAILS[1..2]);
+        }
+    }
+
+    #[test]
+    fn round_trips_any_prefix_run() {
+        const PREFIXES: [u8; 8] = [0x26, 0x2E, 0x36, 0x3E, 0xF0, 0xF1, 0xF2, 0xF3];
+        // Every run of up to three prefixes, repeats and all
+        let mut runs = vec![vec![]];
+        let mut last: Vec<Vec<u8>> = vec![vec![]];
+        for _ in 0..3 {
+            last = last
+                .iter()
+                .flat_map(|run| PREFIXES.map(|prefix| [&run[..], &[prefix]].concat()))
+                .collect();
+            runs.extend(last.iter().cloned());
+        }
+        for run in runs {
+            for ins in [
+                &[0xA4][..],
+                &[0x8B, 0x07],
+                &[0xFF, 0x36, 0x34, 0x12],
+                &[0x90],
+            ] {
+                let bytes = [&run[..], ins].concat();
+                let decoded = decode_bytes(&bytes, IP).unwrap();
+                assert_eq!(encode_as(&decoded, IP, decoded.opcode), Ok(bytes.clone()));
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_prefix_runs_too_long_to_keep() {
+        let mut bytes = vec![0x26; PrefixBytes::CAPACITY + 1];
+        bytes.push(0xA4);
+        let ins = decode_bytes(&bytes, IP).unwrap();
+        assert_eq!(
+            encode_as(&ins, IP, ins.opcode),
+            Err(EncodeError::LostPrefixes)
+        );
+        assert_eq!(encode(&ins, IP), Ok(vec![0x26, 0xA4]));
+    }
+
+    #[test]
+    fn shortest_encoding_decodes_the_same() {
+        for (_, ins) in every_instruction(&[], &TAILS) {
+            let encoded = encode(&ins, IP).unwrap();
+            assert!(encoded.len() <= ins.length as usize, "{}", ins);
+            let again = decode_bytes(&encoded, IP).unwrap();
+            assert_eq!(again.length as usize, encoded.len(), "{}", ins);
+            assert_eq!(again.mnemonic, ins.mnemonic, "{}", ins);
+            assert_eq!(again.prefixes, ins.prefixes, "{}", ins);
+            let [dest, src] = normalized(&ins);
+            let swapped = matches!(ins.mnemonic, Mnemonic::Xchg | Mnemonic::Test)
+                && normalized(&again) == [src, dest];
+            assert!(swapped || normalized(&again) == [dest, src], "{}", ins);
+        }
+    }
+
+    #[test]
+    fn picks_the_shortest_form() {
+        // MOV AX, BX has 89h and 8Bh forms, the lower opcode wins
+        assert_eq!(encode_one(&[0x8B, 0xC3]), [0x89, 0xD8]);
+        // ADD BX, 1 fits the sign-extended imm8
+        assert_eq!(encode_one(&[0x81, 0xC3, 0x01, 0x00]), [0x83, 0xC3, 0x01]);
+        assert_eq!(
+            encode_one(&[0x81, 0xC3, 0x80, 0x00]),
+            [0x81, 0xC3, 0x80, 0x00]
+        );
+        // Accumulator forms drop the ModRM byte
+        assert_eq!(encode_one(&[0x80, 0xC0, 0x05]), [0x04, 0x05]);
+        // Zero and small displacements shrink, [BP] keeps a byte
+        assert_eq!(encode_one(&[0x8B, 0x87, 0x00, 0x00]), [0x8B, 0x07]);
+        assert_eq!(encode_one(&[0x8B, 0x86, 0x00, 0x00]), [0x8B, 0x46, 0x00]);
+        assert_eq!(encode_one(&[0x8B, 0x84, 0x10, 0x00]), [0x8B, 0x44, 0x10]);
+        // Jumps go short when the target is in reach
+        assert_eq!(encode_one(&[0xE9, 0x10, 0x00]), [0xEB, 0x11]);
+        assert_eq!(encode_one(&[0xE9, 0x00, 0x10]), [0xE9, 0x00, 0x10]);
+        // Undocumented aliases come out as the real opcode
+        assert_eq!(encode_one(&[0x64, 0x05]), [0x74, 0x05]);
+        assert_eq!(encode_one(&[0xC1]), [0xC3]);
+        assert_eq!(
+            encode_one(&[0xF7, 0xCB, 0x34, 0x12]),
+            [0xF7, 0xC3, 0x34, 0x12]
+        );
+        // Prefixes come out once each, in canonical order
+        assert_eq!(encode_one(&[0x26, 0xF3, 0x2E, 0xA4]), [0xF3, 0x2E, 0xA4]);
+        // XCHG and TEST take their operands either way round
+        assert_eq!(encode_one(&[0x87, 0xC1]), [0x91]);
+        assert_eq!(encode_one(&[0x87, 0xC8]), [0x91]);
+        assert_eq!(encode_one(&[0x85, 0xC3]), [0x85, 0xC3]);
+        // XCHG CX, BX goes in with CX in REG, TEST CX, BX with CX in R/M
+        assert_eq!(encode_one(&[0x87, 0xD9]), [0x87, 0xCB]);
+        assert_eq!(encode_one(&[0x85, 0xD9]), [0x85, 0xD9]);
+    }
+
+    #[test]
+    fn keeps_decoded_sizes() {
+        let sized = |bytes: &[u8]| encode_sized(&decode_bytes(bytes, IP).unwrap()).unwrap();
+        // Displacements, word immediates and jumps stay as wide as they were
+        assert_eq!(sized(&[0x8B, 0x87, 0x00, 0x00]), [0x8B, 0x87, 0x00, 0x00]);
+        assert_eq!(sized(&[0x8B, 0x47, 0x00]), [0x8B, 0x47, 0x00]);
+        assert_eq!(sized(&[0x81, 0xC3, 0x01, 0x00]), [0x81, 0xC3, 0x01, 0x00]);
+        assert_eq!(sized(&[0xE9, 0x10, 0x00]), [0xE9, 0x10, 0x00]);
+        assert_eq!(sized(&[0xEB, 0x10]), [0xEB, 0x10]);
+        // but the opcode is still the shortest one
+        assert_eq!(sized(&[0x81, 0xC0, 0x01, 0x00]), [0x05, 0x01, 0x00]);
+        assert_eq!(sized(&[0x8B, 0x06, 0x12, 0x34]), [0xA1, 0x12, 0x34]);
+        assert_eq!(sized(&[0xFF, 0xC0]), [0x40]);
+        assert_eq!(sized(&[0x26, 0xF3, 0xA4]), [0xF3, 0x26, 0xA4]);
+    }
+
+    #[test]
+    fn forces_an_opcode() {
+        let ins = decode_bytes(&[0x89, 0xD8], IP).unwrap();
+        assert_eq!(encode_as(&ins, IP, 0x8B), Ok(vec![0x8B, 0xC3]));
+        assert_eq!(encode_as(&ins, IP, 0x88), Err(EncodeError::NoEncoding));
+        let ins = decode_bytes(&[0x83, 0xC3, 0x01], IP).unwrap();
+        assert_eq!(encode_as(&ins, IP, 0x81), Ok(vec![0x81, 0xC3, 0x01, 0x00]));
+    }
+
+    #[test]
+    fn keeps_what_the_decoder_saw() {
+        for bytes in [
+            &[0x26, 0xF3, 0xA4][..],
+            &[0x26, 0x26, 0xA4],
+            &[0xF6, 0xC8, 0x12],
+            &[0xC7, 0x1F, 0x34, 0x12],
+            &[0x64, 0xFE],
+        ] {
+            let ins = decode_bytes(bytes, IP).unwrap();
+            assert_eq!(encode_as(&ins, IP, ins.opcode).as_deref(), Ok(bytes));
+            assert_eq!(original_bytes(&ins).as_deref(), Ok(bytes));
+        }
+    }
+
+    #[test]
+    fn rejects_unreachable_targets() {
+        let ins = decode_bytes(&[0x74, 0x05], IP).unwrap();
+        assert_eq!(encode(&ins, 0x1000), Err(EncodeError::OutOfRange));
+        assert_eq!(encode(&ins, IP), Ok(vec![0x74, 0x05]));
+    }
+}
@@ -0,0 +1,197 @@
+use std::fmt;
+
+use super::{Symbols, fmt_prefixes, fmt_signed};
+use crate::core::instruction::*;
+
+// AT&T syntax as GNU as accepts it with .code16: source operand first, sizes
+// as mnemonic suffixes, far transfers as lcall/ljmp/lret.
+pub fn fmt_instruction(
+    ins: &Instruction,
+    symbols: Option<Symbols>,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    fmt_prefixes(ins, f)?;
+    if ins.is_nop() {
+        return write!(f, "nop");
+    }
+    write!(f, "{}", mnemonic(ins))?;
+    if let Some(width) = ins.operands().find_map(|op| match op {
+        Operand::Mem(mem) => Some(mem.width),
+        _ => None,
+    }) && ins.needs_size()
+        && !matches!(ins.mnemonic, Mnemonic::Call | Mnemonic::Jmp | Mnemonic::Esc)
+    {
+        write!(f, "{}", suffix(ins.mnemonic, width))?;
+    }
+
+    if matches!(ins.mnemonic, Mnemonic::Aam | Mnemonic::Aad)
+        && ins.operands[0] == Some(Operand::Imm(Immediate::Byte(10)))
+    {
+        return Ok(());
+    }
+
+    for (i, operand) in ins.operands.iter().rev().flatten().enumerate() {
+        write!(f, "{}", if i == 0 { " " } else { "," })?;
+        fmt_operand(ins, operand, symbols, f)?;
+    }
+    Ok(())
+}
+
+fn mnemonic(ins: &Instruction) -> String {
+    use Mnemonic::*;
+    let far = ins.is_far_indirect() || matches!(ins.operands[0], Some(Operand::FarPtr { .. }));
+    // as keeps the historical AT&T mix-up of the reversed subtract and divide
+    // when ST(i) is the destination, so DC E8+i (Intel FSUB ST(i), ST(0)) is fsubr
+    let st_destination =
+        matches!(ins.opcode, 0xDC | 0xDE) && matches!(ins.operands[0], Some(Operand::St(_)));
+    let mnemonic = match ins.mnemonic {
+        Fsub if st_destination => Fsubr,
+        Fsubr if st_destination => Fsub,
+        Fdiv if st_destination => Fdivr,
+        Fdivr if st_destination => Fdiv,
+        Fsubp => Fsubrp,
+        Fsubrp => Fsubp,
+        Fdivp => Fdivrp,
+        Fdivrp => Fdivp,
+        mnemonic => mnemonic,
+    };
+    match mnemonic {
+        Call | Jmp if far => format!("l{}", mnemonic),
+        Retf => "lret".to_string(),
+        Cbw => "cbtw".to_string(),
+        Cwd => "cwtd".to_string(),
+        _ => mnemonic.to_string(),
+    }
+}
+
+fn suffix(mnemonic: Mnemonic, width: Width) -> &'static str {
+    use Mnemonic::*;
+    let integer = matches!(
+        mnemonic,
+        Fiadd | Fimul | Ficom | Ficomp | Fisub | Fisubr | Fidiv | Fidivr | Fild | Fist | Fistp
+    );
+    match width {
+        Width::Byte => "b",
+        Width::Word if integer => "s",
+        Width::Word => "w",
+        Width::Dword if integer => "l",
+        Width::Dword => "s",
+        Width::Qword if integer => "ll",
+        Width::Qword => "l",
+        Width::Tword if matches!(mnemonic, Fbld | Fbstp) => "",
+        Width::Tword => "t",
+        Width::Environment | Width::State => "",
+    }
+}
+
+fn fmt_operand(
+    ins: &Instruction,
+    operand: &Operand,
+    symbols: Option<Symbols>,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    // Indirect branch targets are marked with a star
+    let indirect = matches!(ins.mnemonic, Mnemonic::Call | Mnemonic::Jmp);
+    match operand {
+        Operand::Reg(Register::DX) if matches!(ins.mnemonic, Mnemonic::In | Mnemonic::Out) => {
+            write!(f, "(%dx)")
+        }
+        Operand::Reg(reg) if indirect => write!(f, "*%{}", reg),
+        Operand::Reg(reg) => write!(f, "%{}", reg),
+        Operand::SegReg(sreg) => write!(f, "%{}", sreg),
+        Operand::Mem(mem) => {
+            if indirect {
+                write!(f, "*")?;
+            }
+            fmt_memory(mem, f)
+        }
+        Operand::Imm(Immediate::Byte(imm)) => write!(f, "$0x{:x}", imm),
+        Operand::Imm(Immediate::Word(imm)) => write!(f, "$0x{:x}", imm),
+        Operand::Imm(Immediate::SignExtended(imm)) => {
+            write!(f, "$")?;
+            fmt_signed(f, *imm as i16)
+        }
+        Operand::RelTarget { target, .. } => match symbols.and_then(|name| name(*target)) {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "0x{:x}", target),
+        },
+        Operand::FarPtr { segment, offset } => write!(f, "$0x{:x},$0x{:x}", segment, offset),
+        Operand::St(0) => write!(f, "%st"),
+        Operand::St(i) => write!(f, "%st({})", i),
+    }
+}
+
+// disp(%base,%index), or a bare address
+fn fmt_memory(mem: &Memory, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if let Some(segment) = mem.segment {
+        write!(f, "%{}:", segment)?;
+    }
+    let registers: Vec<String> = [mem.base, mem.index]
+        .iter()
+        .flatten()
+        .map(|reg| format!("%{}", reg))
+        .collect();
+    if registers.is_empty() {
+        return write!(f, "0x{:x}", mem.disp.value() as u16);
+    }
+    if mem.disp != Displacement::None {
+        write!(f, "{}", mem.disp.value())?;
+    }
+    write!(f, "({})", registers.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::decoder::decode_bytes;
+    use crate::core::format::Syntax;
+
+    fn att(bytes: &[u8]) -> String {
+        let ins = decode_bytes(bytes, 0x100).unwrap();
+        ins.display(Syntax::Att).to_string()
+    }
+
+    #[test]
+    fn writes_source_first() {
+        assert_eq!(
+            att(&[0xC7, 0x42, 0xFC, 0x34, 0x12]),
+            "movw $0x1234,-4(%bp,%si)"
+        );
+        assert_eq!(att(&[0x9A, 0x20, 0x00, 0x00, 0x10]), "lcall $0x1000,$0x20");
+        assert_eq!(att(&[0xEA, 0x20, 0x00, 0x00, 0x10]), "ljmp $0x1000,$0x20");
+        assert_eq!(att(&[0x26, 0x8B, 0x07]), "mov %es:(%bx),%ax");
+        assert_eq!(att(&[0xFF, 0x1F]), "lcall *(%bx)");
+        assert_eq!(att(&[0xFF, 0xE3]), "jmp *%bx");
+    }
+
+    #[test]
+    fn suffixes_memory_operands_without_a_register() {
+        assert_eq!(att(&[0xFE, 0x07]), "incb (%bx)");
+        assert_eq!(att(&[0xFF, 0x07]), "incw (%bx)");
+        assert_eq!(att(&[0x8B, 0x07]), "mov (%bx),%ax");
+        assert_eq!(att(&[0xD3, 0x27]), "shlw %cl,(%bx)");
+        // 8087 real and integer operands
+        assert_eq!(att(&[0xD9, 0x07]), "flds (%bx)");
+        assert_eq!(att(&[0xDD, 0x07]), "fldl (%bx)");
+        assert_eq!(att(&[0xDB, 0x2F]), "fldt (%bx)");
+        assert_eq!(att(&[0xDE, 0x07]), "fiadds (%bx)");
+        assert_eq!(att(&[0xDA, 0x07]), "fiaddl (%bx)");
+        assert_eq!(att(&[0xDF, 0x2F]), "fildll (%bx)");
+        assert_eq!(att(&[0xDF, 0x27]), "fbld (%bx)");
+    }
+
+    #[test]
+    fn swaps_reversed_8087_operations_into_st_i() {
+        // Intel fsub st1, st0 and friends
+        assert_eq!(att(&[0xDC, 0xE9]), "fsubr %st,%st(1)");
+        assert_eq!(att(&[0xDC, 0xE1]), "fsub %st,%st(1)");
+        assert_eq!(att(&[0xDC, 0xF9]), "fdivr %st,%st(1)");
+        assert_eq!(att(&[0xDC, 0xF1]), "fdiv %st,%st(1)");
+        assert_eq!(att(&[0xDE, 0xE9]), "fsubrp %st,%st(1)");
+        assert_eq!(att(&[0xDE, 0xE1]), "fsubp %st,%st(1)");
+        assert_eq!(att(&[0xDE, 0xF9]), "fdivrp %st,%st(1)");
+        assert_eq!(att(&[0xDE, 0xF1]), "fdivp %st,%st(1)");
+        // ST(0) as the destination is left alone
+        assert_eq!(att(&[0xD8, 0xE1]), "fsub %st(1),%st");
+        assert_eq!(att(&[0xD8, 0xF9]), "fdivr %st(1),%st");
+    }
+}
@@ -0,0 +1,256 @@
+use std::fmt;
+
+use super::{Symbols, fmt_prefixes, fmt_signed};
+use crate::core::encoder::{encode_sized, original_bytes};
+use crate::core::instruction::*;
+
+// Intel syntax as NASM accepts it. Where NASM would pick a shorter encoding
+// than the one decoded, the text forces the longer one (strict, near, [byte
+// ...]) so the output reassembles to the same bytes. Encodings NASM can't be
+// told to pick, like 8Bh for a register to register MOV or the undocumented
+// aliases, come out as db with the instruction in a comment.
+pub fn fmt_instruction(
+    ins: &Instruction,
+    symbols: Option<Symbols>,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    if !nasm_can_encode(ins)
+        && let Ok(bytes) = original_bytes(ins)
+    {
+        let values: Vec<String> = bytes.iter().map(|b| format!("0x{:x}", b)).collect();
+        write!(f, "db {} ; ", values.join(", "))?;
+    }
+    fmt_prefixes(ins, f)?;
+    if ins.is_nop() {
+        return write!(f, "nop");
+    }
+    write!(f, "{}", ins.mnemonic)?;
+
+    // AAM and AAD with the default base 10 have their own short form
+    if matches!(ins.mnemonic, Mnemonic::Aam | Mnemonic::Aad)
+        && ins.operands[0] == Some(Operand::Imm(Immediate::Byte(10)))
+    {
+        return Ok(());
+    }
+
+    for (i, operand) in ins.operands().enumerate() {
+        write!(f, "{}", if i == 0 { " " } else { ", " })?;
+        match operand {
+            Operand::Mem(mem) => {
+                if ins.is_far_indirect() {
+                    write!(f, "far ")?;
+                } else if ins.needs_size() {
+                    write!(f, "{} ", size_keyword(mem.width))?;
+                }
+                write!(f, "{}", mem)?;
+            }
+            Operand::Imm(Immediate::Word(imm)) if has_short_immediate(ins, *imm) => {
+                write!(f, "strict word {}", operand)?;
+            }
+            Operand::RelTarget { target, .. } => {
+                match ins.opcode {
+                    0xEB => write!(f, "short ")?,
+                    0xE9 => write!(f, "near ")?,
+                    _ => {}
+                }
+                match symbols.and_then(|name| name(*target)) {
+                    Some(name) => write!(f, "{}", name)?,
+                    None => write!(f, "{}", operand)?,
+                }
+            }
+            _ => write!(f, "{}", operand)?,
+        }
+    }
+    Ok(())
+}
+
+// False where the text doesn't assemble back to the same bytes: ESC, SETMO
+// and F1h have no name in NASM, and otherwise NASM picks the shortest form
+// for the sizes the text spells out, which need not be the decoded one
+fn nasm_can_encode(ins: &Instruction) -> bool {
+    !matches!(ins.mnemonic, Mnemonic::Esc | Mnemonic::Setmo)
+        && ins.prefixes.lock != Some(LockPrefix::LockAlias)
+        && encode_sized(ins).ok() == original_bytes(ins).ok()
+}
+
+// A word immediate NASM would shrink to the sign-extended 83h form
+fn has_short_immediate(ins: &Instruction, imm: u16) -> bool {
+    use Mnemonic::*;
+    matches!(ins.mnemonic, Add | Or | Adc | Sbb | And | Sub | Xor | Cmp)
+        && i8::try_from(imm as i16).is_ok()
+}
+
+fn size_keyword(width: Width) -> &'static str {
+    match width {
+        Width::Byte => "byte",
+        Width::Word => "word",
+        Width::Dword => "dword",
+        Width::Qword => "qword",
+        Width::Tword => "tword",
+        Width::Environment | Width::State => "",
+    }
+}
+
+impl fmt::Display for Immediate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Immediate::Byte(imm) => write!(f, "0x{:x}", imm),
+            Immediate::Word(imm) => write!(f, "0x{:x}", imm),
+            Immediate::SignExtended(imm) => fmt_signed(f, *imm as i16),
+        }
+    }
+}
+
+impl fmt::Display for Memory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        // NASM drops a zero disp8 and shrinks a disp16 that fits in a byte,
+        // unless told otherwise. [bp] has no encoding without a displacement.
+        let bp_only = self.base == Some(Register::BP) && self.index.is_none();
+        match self.disp {
+            Displacement::Byte(0) if !bp_only => write!(f, "byte ")?,
+            Displacement::Word(disp)
+                if (self.base.is_some() || self.index.is_some()) && i8::try_from(disp).is_ok() =>
+            {
+                write!(f, "word ")?
+            }
+            _ => {}
+        }
+        if let Some(segment) = self.segment {
+            write!(f, "{}:", segment)?;
+        }
+
+        let registers: Vec<String> = [self.base, self.index]
+            .iter()
+            .flatten()
+            .map(|reg| reg.to_string())
+            .collect();
+        if registers.is_empty() {
+            // Direct address
+            return write!(f, "0x{:x}]", self.disp.value() as u16);
+        }
+        write!(f, "{}", registers.join("+"))?;
+        match self.disp {
+            Displacement::None => {}
+            disp if disp.value() < 0 => fmt_signed(f, disp.value())?,
+            disp => {
+                write!(f, "+")?;
+                fmt_signed(f, disp.value())?;
+            }
+        }
+        write!(f, "]")
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Reg(reg) => write!(f, "{}", reg),
+            Operand::SegReg(sreg) => write!(f, "{}", sreg),
+            Operand::Mem(mem) => write!(f, "{}", mem),
+            Operand::Imm(imm) => write!(f, "{}", imm),
+            Operand::RelTarget { target, .. } => write!(f, "0x{:04x}", target),
+            Operand::FarPtr { segment, offset } => write!(f, "0x{:04x}:0x{:04x}", segment, offset),
+            Operand::St(i) => write!(f, "st{}", i),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::decoder::decode_bytes;
+    use crate::core::format::Syntax;
+
+    fn nasm(bytes: &[u8]) -> String {
+        let ins = decode_bytes(bytes, 0x100).unwrap();
+        ins.display(Syntax::Intel).to_string()
+    }
+
+    // The listing text for each line, `db` and all
+    fn nasm_lines(image: &[u8]) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut offset = 0;
+        while offset < image.len() {
+            let ins = decode_bytes(&image[offset..], 0x100 + offset as u16).unwrap();
+            lines.push(ins.display(Syntax::Intel).to_string());
+            offset += ins.length as usize;
+        }
+        lines
+    }
+
+    #[test]
+    fn writes_what_nasm_has_no_name_for_as_db() {
+        assert_eq!(nasm(&[0xD9, 0x08]), "db 0xd9, 0x8 ; esc 0x9, word [bx+si]");
+        assert_eq!(nasm(&[0xD0, 0xF0]), "db 0xd0, 0xf0 ; setmo al, 0x1");
+        assert_eq!(nasm(&[0xF1, 0xA4]), "db 0xf1, 0xa4 ; lock movsb");
+    }
+
+    #[test]
+    fn writes_what_nasm_encodes_differently_as_db() {
+        let cases: [(&[u8], &str); 17] = [
+            // NASM writes prefixes once each, REP before the segment
+            (&[0x26, 0xF3, 0xA4], "rep es movsb"),
+            (&[0x26, 0x26, 0xA4], "es movsb"),
+            // Accumulator forms
+            (&[0x80, 0xC0, 0x12], "add al, 0x12"),
+            (&[0x81, 0xC0, 0x34, 0x12], "add ax, 0x1234"),
+            (&[0x81, 0xC0, 0xFF, 0xFF], "add ax, strict word 0xffff"),
+            (&[0xF6, 0xC0, 0x12], "test al, 0x12"),
+            (&[0xF7, 0xC0, 0x34, 0x12], "test ax, 0x1234"),
+            (&[0x8B, 0x06, 0x12, 0x34], "mov ax, [0x3412]"),
+            // Register forms
+            (&[0xC6, 0xC0, 0x12], "mov al, 0x12"),
+            (&[0xC6, 0xC3, 0x12], "mov bl, 0x12"),
+            (&[0xFF, 0xC0], "inc ax"),
+            (&[0xFF, 0xF0], "push ax"),
+            (&[0x8F, 0xC0], "pop ax"),
+            (&[0x8B, 0xC3], "mov ax, bx"),
+            (&[0x87, 0xC1], "xchg cx, ax"),
+            // Aliases and ignored REG values
+            (&[0xF6, 0xC8, 0x12], "test al, 0x12"),
+            (&[0x82, 0xC0, 0x12], "add al, 0x12"),
+        ];
+        for (bytes, text) in cases {
+            let values: Vec<String> = bytes.iter().map(|b| format!("0x{:x}", b)).collect();
+            let db = format!("db {} ; {}", values.join(", "), text);
+            assert_eq!(nasm(bytes), db, "{:02X?}", bytes);
+        }
+    }
+
+    #[test]
+    fn keeps_text_nasm_reassembles() {
+        let image = [
+            0xC7, 0x42, 0xFC, 0x34, 0x12, // mov word [bp+si-0x4], 0x1234
+            0xF3, 0xA4, // rep movsb
+            0xE9, 0x19, 0x00, // jmp near 0x0123
+            0xF3, 0x26, 0xA4, // rep es movsb
+            0x89, 0xD8, // mov ax, bx
+            0x91, // xchg ax, cx
+            0x85, 0xD9, // test cx, bx
+            0x83, 0xC3, 0x12, // add bx, 0x12
+            0x81, 0xC3, 0x12, 0x00, // add bx, strict word 0x12
+            0x05, 0x12, 0x00, // add ax, strict word 0x12
+            0x8B, 0x47, 0x00, // mov ax, [byte bx+0x0]
+            0x8B, 0x46, 0x00, // mov ax, [bp+0x0]
+            0xEB, 0x00, // jmp short 0x0124
+        ];
+        assert_eq!(
+            nasm_lines(&image),
+            [
+                "mov word [bp+si-0x4], 0x1234",
+                "rep movsb",
+                "jmp near 0x0123",
+                "rep es movsb",
+                "mov ax, bx",
+                "xchg ax, cx",
+                "test cx, bx",
+                "add bx, 0x12",
+                "add bx, strict word 0x12",
+                "add ax, strict word 0x12",
+                "mov ax, [byte bx+0x0]",
+                "mov ax, [bp+0x0]",
+                "jmp short 0x0124",
+            ]
+        );
+    }
+}
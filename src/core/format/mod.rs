@@ -0,0 +1,138 @@
+mod att;
+mod intel;
+
+use std::fmt;
+
+use clap::ValueEnum;
+
+use crate::core::instruction::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Syntax {
+    // NASM flavoured Intel syntax
+    #[default]
+    Intel,
+    // GNU as syntax
+    Att,
+}
+
+// Name for an address, used in place of the number for branch targets
+pub type Symbols<'a> = &'a dyn Fn(u16) -> Option<String>;
+
+// An instruction paired with the syntax to print it in, see Instruction::display
+pub struct Formatted<'a> {
+    ins: &'a Instruction,
+    syntax: Syntax,
+    symbols: Option<Symbols<'a>>,
+}
+
+impl<'a> Formatted<'a> {
+    pub fn with_symbols(mut self, symbols: Symbols<'a>) -> Self {
+        self.symbols = Some(symbols);
+        self
+    }
+}
+
+impl fmt::Display for Formatted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.syntax {
+            Syntax::Intel => intel::fmt_instruction(self.ins, self.symbols, f),
+            Syntax::Att => att::fmt_instruction(self.ins, self.symbols, f),
+        }
+    }
+}
+
+impl Instruction {
+    pub fn display(&self, syntax: Syntax) -> Formatted<'_> {
+        Formatted {
+            ins: self,
+            syntax,
+            symbols: None,
+        }
+    }
+
+    // A memory operand needs a size keyword or suffix unless a register
+    // operand gives it away. CL as a shift count doesn't.
+    fn needs_size(&self) -> bool {
+        use Mnemonic::*;
+        match self.mnemonic {
+            Lea | Lds | Les => return false,
+            Fldenv | Fnstenv | Frstor | Fnsave | Fldcw | Fnstcw | Fnstsw => return false,
+            _ => {}
+        }
+        !self.operands().any(|op| match op {
+            Operand::Reg(Register::CL) => !is_shift(self.mnemonic),
+            Operand::Reg(_) | Operand::SegReg(_) => true,
+            _ => false,
+        })
+    }
+
+    fn is_far_indirect(&self) -> bool {
+        matches!(self.mnemonic, Mnemonic::Call | Mnemonic::Jmp)
+            && matches!(self.operands[0], Some(Operand::Mem(mem)) if mem.width == Width::Dword)
+    }
+}
+
+// Intel syntax
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        intel::fmt_instruction(self, None, f)
+    }
+}
+
+// LOCK, REP and a segment override that has no memory operand to carry it
+fn fmt_prefixes(ins: &Instruction, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if ins.prefixes.lock.is_some() {
+        write!(f, "lock ")?;
+    }
+    match ins.prefixes.rep {
+        Some(RepInstruction::Repz) if is_compare_string(ins.mnemonic) => write!(f, "repe ")?,
+        Some(RepInstruction::Repz) => write!(f, "rep ")?,
+        Some(RepInstruction::Repnz) => write!(f, "repne ")?,
+        None => {}
+    }
+    if let Some(segment) = ins.prefixes.segment
+        && !ins.operands().any(|op| matches!(op, Operand::Mem(_)))
+    {
+        write!(f, "{} ", segment)?;
+    }
+    Ok(())
+}
+
+fn is_compare_string(mnemonic: Mnemonic) -> bool {
+    use Mnemonic::*;
+    matches!(mnemonic, Cmpsb | Cmpsw | Scasb | Scasw)
+}
+
+fn is_shift(mnemonic: Mnemonic) -> bool {
+    use Mnemonic::*;
+    matches!(mnemonic, Rol | Ror | Rcl | Rcr | Shl | Shr | Setmo | Sar)
+}
+
+// Signed hex, for displacements and sign-extended immediates
+fn fmt_signed(f: &mut fmt::Formatter<'_>, value: i16) -> fmt::Result {
+    if value < 0 {
+        write!(f, "-0x{:x}", value.unsigned_abs())
+    } else {
+        write!(f, "0x{:x}", value)
+    }
+}
+
+impl fmt::Display for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The variant names are the mnemonics, only capitalised
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
+
+impl fmt::Display for SegmentRegister {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_lowercase())
+    }
+}
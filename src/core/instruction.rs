@@ -2,6 +2,8 @@ use std::fmt;
 
 use num_enum::TryFromPrimitive;
 
+use crate::core::opcodes::{self, OpcodeInfo};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u8)]
 pub enum Register {
@@ -26,6 +28,12 @@ pub enum Register {
     DI,
 }
 
+impl Register {
+    pub fn is_16bit(&self) -> bool {
+        *self as u8 >= Register::AX as u8
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u8)]
 pub enum SegmentRegister {
@@ -35,22 +43,12 @@ pub enum SegmentRegister {
     DS,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Registers {
-    Gpr(Register),
-    Seg(SegmentRegister),
-}
-
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum Immediate {
     Byte(u8),
     Word(u16),
-}
-
-#[derive(Clone, Copy, PartialEq, Eq)]
-pub enum MemoryAddress {
-    Byte(u32),
-    Word(u32),
+    // imm8 that the CPU sign-extends to 16 bits before use (83h group)
+    SignExtended(i8),
 }
 
 impl fmt::Debug for Immediate {
@@ -59,141 +57,104 @@ impl fmt::Debug for Immediate {
             Immediate::Byte(val) => write!(f, "Byte(0x{:02X})", val),
 
             Immediate::Word(val) => write!(f, "Word(0x{:02X})", val),
-        }
-    }
-}
-
-impl fmt::Debug for MemoryAddress {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            MemoryAddress::Byte(val) => write!(f, "Byte(0x{:04X})", val),
 
-            MemoryAddress::Word(val) => write!(f, "Word(0x{:04X})", val),
+            Immediate::SignExtended(val) if *val < 0 => {
+                write!(f, "SignExtended(-0x{:02X})", val.unsigned_abs())
+            }
+            Immediate::SignExtended(val) => write!(f, "SignExtended(0x{:02X})", val),
         }
     }
 }
 
+// How much memory an operand covers
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct MovImmToReg {
-    pub dest: Register,
-    pub imm: Immediate,
-    pub length: u8,
+pub enum Width {
+    Byte,
+    Word,
+    Dword,       // Far pointers, 8087 int32/real32
+    Qword,       // 8087 int64/real64
+    Tword,       // 8087 real80/bcd80
+    Environment, // FLDENV/FNSTENV block
+    State,       // FRSTOR/FNSAVE block, environment plus the register stack
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct MovMemToAcc {
-    pub dest: Register,
-    pub mem_addr: MemoryAddress,
-    pub to_acc: bool,
-    pub length: u8,
+impl Width {
+    #[allow(dead_code)]
+    pub fn size_in_bytes(&self) -> u8 {
+        match self {
+            Width::Byte => 1,
+            Width::Word => 2,
+            Width::Dword => 4,
+            Width::Qword => 8,
+            Width::Tword => 10,
+            Width::Environment => 14,
+            Width::State => 94,
+        }
+    }
 }
+
+// The variant records how the displacement was encoded, so [bp+0x0] and
+// [bp+0x0000] stay distinguishable.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Displacement {
-    Zero(u8),
+    None,
     Byte(i8),
     Word(i16),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct MovMemToReg {
-    // Always to REG part in modrm
-    // R/M Part will always encode a Mem addr
-    pub is_16bit: bool,
-    pub decoded_rm: DecodedRMMode,
-    pub decdode_reg: Registers,
-    pub displacement: Displacement,
-    pub length: u8,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum DecodedRMMode {
-    Mem(MemoryMode),
-    Reg(Register),
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct MovRegToRM {
-    // Always to RM part in modrm
-    pub is_16bit: bool,
-    pub is_rm_a_reg: bool,
-    pub decoded_rm: DecodedRMMode,
-    pub decdode_reg: Registers,
-    pub displacement: Displacement,
-    pub length: u8,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct MovSregToFromRM {
-    pub to_rm: bool,
-    pub is_rm_a_reg: bool,
-    pub decoded_rm: DecodedRMMode,
-    pub decdode_reg: Registers,
-    pub displacement: Displacement,
-    pub length: u8,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct MovImmToRM {
-    pub is_16bit: bool,
-    pub is_rm_a_reg: bool,
-    pub decoded_rm: DecodedRMMode,
-    pub displacement: Displacement,
-    pub imm: Immediate,
-    pub length: u8,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum MovInstruction {
-    ImmToReg(MovImmToReg),     // Type 3
-    MemToAcc(MovMemToAcc),     // Type 2
-    MemToReg(MovMemToReg),     // Type 1 8Ah, 8Bh
-    RegToRM(MovRegToRM),       // Type 1 88h, 89h
-    SregToRM(MovSregToFromRM), // Type 5 8Ch
-    RMToSreg(MovSregToFromRM), // Type 5 8Eh
-    ImmToRM(MovImmToRM),       // Type 1 C6h, C7h
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct FillerInstruction {
-    pub length: u8,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct FixedOut {
-    pub is_ax: bool,
-    pub port_number: u8,
-    pub length: u8,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct VariableOut {
-    pub is_ax: bool,
-    pub length: u8,
+impl Displacement {
+    pub fn value(&self) -> i16 {
+        match self {
+            Displacement::None => 0,
+            Displacement::Byte(disp) => *disp as i16,
+            Displacement::Word(disp) => *disp,
+        }
+    }
 }
 
+// [base + index + disp]. With neither base nor index, disp is a direct
+// address. Segment is the override prefix, if there was one.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum OutInstruction {
-    Fixed(FixedOut),
-    Variable(VariableOut),
+pub struct Memory {
+    pub base: Option<Register>,
+    pub index: Option<Register>,
+    pub disp: Displacement,
+    pub segment: Option<SegmentRegister>,
+    pub width: Width,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct FixedIn {
-    pub is_ax: bool,
-    pub port_number: u8,
-    pub length: u8,
+impl Memory {
+    // The override if there is one, else SS for BP-based addresses and DS
+    // for everything else
+    pub fn effective_segment(&self) -> SegmentRegister {
+        match (self.segment, self.base) {
+            (Some(segment), _) => segment,
+            (None, Some(Register::BP)) => SegmentRegister::SS,
+            (None, _) => SegmentRegister::DS,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct VariableIn {
-    pub is_ax: bool,
-    pub length: u8,
+pub enum Operand {
+    Reg(Register),
+    SegReg(SegmentRegister),
+    Mem(Memory),
+    Imm(Immediate),
+    // Relative jump, call or loop: the displacement as encoded, and the
+    // offset it lands on resolved against the IP the instruction was decoded at
+    RelTarget { target: u16, disp: i16 },
+    FarPtr { segment: u16, offset: u16 },
+    // 8087 stack register ST(i)
+    St(u8),
 }
 
+// How an instruction uses an operand, in (destination, source) order
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum InInstruction {
-    Fixed(FixedIn),
-    Variable(VariableIn),
+pub enum OperandAccess {
+    Read,
+    Write,
+    ReadWrite,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -203,195 +164,362 @@ pub enum RepInstruction {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct AAMDBase {
-    pub base: u8,
-    pub length: u8,
-}
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct IntImm8Instruction {
-    pub int_vector: u8,
-    pub length: u8,
+pub enum LockPrefix {
+    Lock,      // F0h
+    LockAlias, // F1h, undocumented
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum IntInstruction {
-    Int3(FillerInstruction),
-    IntImm8(IntImm8Instruction),
-    Into(FillerInstruction),
-}
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct RetIntraInter {
-    pub is_inter: bool,
-    pub length: u8,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Prefixes {
+    pub segment: Option<SegmentRegister>,
+    pub rep: Option<RepInstruction>,
+    pub lock: Option<LockPrefix>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct RetAddIntraInter {
-    pub is_inter: bool,
-    pub data: u16,
-    pub length: u8,
+// The prefix bytes in the order they came, repeats included, so the encoder
+// can give back the same bytes. Prefixes holds what they amount to. Of a
+// longer run only the first CAPACITY bytes are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PrefixBytes {
+    bytes: [u8; PrefixBytes::CAPACITY],
+    len: u8,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum RetInstruction {
-    Ret(RetIntraInter),
-    RetAdd(RetAddIntraInter),
-}
+impl PrefixBytes {
+    pub const CAPACITY: usize = 4;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
-#[repr(u8)]
-pub enum JumpCondition {
-    JO,
-    JNO,
-    JB_JC_JNAE,
-    JAE_JNB_JNC,
-    JE_JZ,
-    JNE_JNZ,
-    JBE_JNA,
-    JA_JNBE,
-    JS,
-    JNS,
-    JP_JPE,
-    JNP_JPO,
-    JL_JNGE,
-    JGE_JNL,
-    JLE_JNG,
-    JG_JNLE,
-}
+    pub fn push(&mut self, byte: u8) {
+        if let Some(slot) = self.bytes.get_mut(self.len as usize) {
+            *slot = byte;
+        }
+        self.len = self.len.saturating_add(1);
+    }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct JumpInstruction {
-    pub jump_condition: JumpCondition,
-    pub signed_disp: i8,
-    pub length: u8,
-}
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct JcxzInstruction {
-    pub signed_disp: i8,
-    pub length: u8,
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // The whole run, None when it was too long to keep
+    pub fn bytes(&self) -> Option<&[u8]> {
+        self.bytes.get(..self.len as usize)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct SegmentOverride {
-    pub segment: SegmentRegister,
+pub enum Mnemonic {
+    // Data transfer
+    Mov,
+    Xchg,
+    Push,
+    Pop,
+    Lea,
+    Lds,
+    Les,
+    In,
+    Out,
+    Xlatb,
+    Lahf,
+    Sahf,
+    Pushf,
+    Popf,
+
+    // Arithmetic and logic
+    Add,
+    Or,
+    Adc,
+    Sbb,
+    And,
+    Sub,
+    Xor,
+    Cmp,
+    Test,
+    Not,
+    Neg,
+    Mul,
+    Imul,
+    Div,
+    Idiv,
+    Inc,
+    Dec,
+    Cbw,
+    Cwd,
+    Aaa,
+    Aad,
+    Aam,
+    Aas,
+    Daa,
+    Das,
+    Salc, // D6h, undocumented
+
+    // Shifts and rotates
+    Rol,
+    Ror,
+    Rcl,
+    Rcr,
+    Shl,
+    Shr,
+    // Undocumented: sets the operand to all ones (SETMO, or SETMOC with CL)
+    Setmo,
+    Sar,
+
+    // String operations, the width is part of the mnemonic
+    Movsb,
+    Movsw,
+    Cmpsb,
+    Cmpsw,
+    Stosb,
+    Stosw,
+    Lodsb,
+    Lodsw,
+    Scasb,
+    Scasw,
+
+    // Control transfer
+    Call,
+    Jmp,
+    Ret,
+    Retf,
+    Int,
+    Int3,
+    Into,
+    Iret,
+    Jo,
+    Jno,
+    Jb,
+    Jae,
+    Je,
+    Jne,
+    Jbe,
+    Ja,
+    Js,
+    Jns,
+    Jp,
+    Jnp,
+    Jl,
+    Jge,
+    Jle,
+    Jg,
+    Jcxz,
+    Loopnz,
+    Loopz,
+    Loop,
+
+    // Processor control
+    Clc,
+    Cld,
+    Cli,
+    Stc,
+    Std,
+    Sti,
+    Cmc,
+    Hlt,
+    Wait,
+    // ESC opcode (D8h-DFh) without a known 8087 meaning. The CPU still
+    // computes the effective address of a memory operand and puts it on the bus.
+    Esc,
+
+    // 8087 arithmetic on ST(0) and a real operand or ST(i)
+    Fadd,
+    Fmul,
+    Fcom,
+    Fcomp,
+    Fsub,
+    Fsubr,
+    Fdiv,
+    Fdivr,
+    Faddp,
+    Fmulp,
+    Fsubp,
+    Fsubrp,
+    Fdivp,
+    Fdivrp,
+    Fcompp,
+
+    // 8087 arithmetic on ST(0) and an integer operand
+    Fiadd,
+    Fimul,
+    Ficom,
+    Ficomp,
+    Fisub,
+    Fisubr,
+    Fidiv,
+    Fidivr,
+
+    // 8087 data transfer
+    Fld,
+    Fst,
+    Fstp,
+    Fild,
+    Fist,
+    Fistp,
+    Fbld,
+    Fbstp,
+    Fxch,
+    Ffree,
+
+    // 8087 control and environment
+    Fldenv,
+    Fldcw,
+    Fnstenv,
+    Fnstcw,
+    Frstor,
+    Fnsave,
+    Fnstsw,
+    Fneni,
+    Fndisi,
+    Fnclex,
+    Fninit,
+    Fdecstp,
+    Fincstp,
+    Fnop,
+
+    // 8087 operations on ST(0), or ST(0) and ST(1)
+    Fchs,
+    Fabs,
+    Ftst,
+    Fxam,
+    Fld1,
+    Fldl2t,
+    Fldl2e,
+    Fldpi,
+    Fldlg2,
+    Fldln2,
+    Fldz,
+    F2xm1,
+    Fyl2x,
+    Fptan,
+    Fpatan,
+    Fxtract,
+    Fprem,
+    Fyl2xp1,
+    Fsqrt,
+    Frndint,
+    Fscale,
+}
+
+// A fully decoded instruction: the mnemonic and up to two explicit operands
+// in Intel (destination, source) order, plus any prefixes in front of it.
+// Opcode is the byte after the prefixes, which tells apart encodings of the
+// same instruction. Together with the raw prefix and ModRM bytes it lets the
+// encoder reproduce forms that mean the same, like F6h /1 for TEST. Length
+// counts the prefix bytes as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction {
+    pub prefixes: Prefixes,
+    pub prefix_bytes: PrefixBytes,
+    pub opcode: u8,
+    // None for opcodes without a ModRM byte
+    pub modrm: Option<u8>,
+    pub mnemonic: Mnemonic,
+    pub operands: [Option<Operand>; 2],
     pub length: u8,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
-#[repr(u8)]
-pub enum MemoryMode {
-    BX_SI,
-    BX_DI,
-    BP_SI,
-    BP_DI,
-    SI,
-    DI,
-    DISP16,
-    BX,
+impl Instruction {
+    pub fn operands(&self) -> impl Iterator<Item = &Operand> {
+        self.operands.iter().flatten()
+    }
 
-    BX_SI_DISP8,
-    BX_DI_DISP8,
-    BP_SI_DISP8,
-    BP_DI_DISP8,
-    SI_DISP8,
-    DI_DISP8,
-    BP_DISP8,
-    BX_DISP8,
-
-    BX_SI_DISP16,
-    BX_DI_DISP16,
-    BP_SI_DISP16,
-    BP_DI_DISP16,
-    SI_DISP16,
-    DI_DISP16,
-    BP_DISP16,
-    BX_DIS168,
-}
+    // Opcode table entry the instruction was decoded from, None for 8087 instructions
+    pub fn info(&self) -> Option<&'static OpcodeInfo> {
+        opcodes::lookup(self)
+    }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum RMMode {
-    Mem(MemoryMode),
-    Reg(u8),
-}
+    // True for opcodes the 8086 executes but Intel never documented. A strict
+    // decoder or disassembler can use this to reject or annotate them.
+    pub fn is_undocumented(&self) -> bool {
+        // 60h-6Fh mirror 70h-7Fh, C0h/C1h/C8h/C9h alias the RET forms, 0Fh is POP CS
+        let alias_opcode = matches!(self.opcode, 0x60..=0x6F | 0xC0 | 0xC1 | 0xC8 | 0xC9 | 0x0F);
+        // F6h/F7h /1 repeat TEST, C6h/C7h ignore REG altogether
+        let reg = self.modrm.map(|modrm| (modrm >> 3) & 0b111);
+        let alias_reg = match self.opcode {
+            0xF6 | 0xF7 => reg == Some(1),
+            0xC6 | 0xC7 => reg != Some(0),
+            _ => false,
+        };
+        alias_opcode
+            || alias_reg
+            || matches!(self.mnemonic, Mnemonic::Setmo | Mnemonic::Salc)
+            || self.prefixes.lock == Some(LockPrefix::LockAlias)
+    }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum DisplacementMode {
-    ZERO,
-    BYTE,
-    WORD,
-}
+    // 90h, XCHG AX, AX, is what assemblers emit for NOP
+    pub fn is_nop(&self) -> bool {
+        self.opcode == 0x90
+    }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct ModRM {
-    pub displacement_mode: DisplacementMode,
-    pub reg_part: u8,
-    pub rm_mode: RMMode,
-}
+    // Resolves the CS:IP a direct jump, call or loop lands on when the
+    // instruction runs at cs:ip, None for everything else including the
+    // indirect forms
+    #[allow(dead_code)]
+    pub fn branch_target(&self, cs: u16, ip: u16) -> Option<(u16, u16)> {
+        match self.operands[0] {
+            Some(Operand::RelTarget { disp, .. }) => {
+                let next = ip.wrapping_add(self.length as u16);
+                Some((cs, next.wrapping_add(disp as u16)))
+            }
+            Some(Operand::FarPtr { segment, offset }) => Some((segment, offset)),
+            _ => None,
+        }
+    }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
-#[repr(u8)]
-pub enum LoopCondition {
-    NZERO_NEQUAL,
-    ZERO_EQUAL,
-    DIRECT,
+    pub fn operand_access(&self) -> (OperandAccess, OperandAccess) {
+        use Mnemonic::*;
+        use OperandAccess::*;
+        match self.mnemonic {
+            // Both operands are read and then overwritten with the other's value
+            Xchg | Fxch => (ReadWrite, ReadWrite),
+            Mov | Lea | Lds | Les | In | Pop | Setmo | Ffree => (Write, Read),
+            Fst | Fstp | Fist | Fistp | Fbstp | Fnstcw | Fnstenv | Fnsave | Fnstsw => (Write, Read),
+            Add | Or | Adc | Sbb | And | Sub | Xor | Not | Neg | Inc | Dec => (ReadWrite, Read),
+            Rol | Ror | Rcl | Rcr | Shl | Shr | Sar => (ReadWrite, Read),
+            // With a single memory operand ST(0) is the implied destination
+            Fadd | Fmul | Fsub | Fsubr | Fdiv | Fdivr if self.operands[1].is_some() => {
+                (ReadWrite, Read)
+            }
+            Faddp | Fmulp | Fsubp | Fsubrp | Fdivp | Fdivrp => (ReadWrite, Read),
+            // TEST and CMP only update flags, MUL/DIV take their destination
+            // implicitly, and everything else only consumes its operands
+            _ => (Read, Read),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct LoopInstruction {
-    pub loop_condition: LoopCondition,
-    pub disp: i8,
-    pub length: u8,
-}
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct LoadInstructionData {
-    pub register: Register,
-    pub displacement: Displacement,
-    pub decoded_mem_mode: DecodedRMMode,
-    pub length: u8,
-}
+#[cfg(test)]
+mod tests {
+    use crate::core::decoder::decode_bytes;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum LoadInstruction {
-    LDS(LoadInstructionData),
-    LES(LoadInstructionData),
-    LEA(LoadInstructionData)
-}
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Instruction {
-    Mov(MovInstruction),
-    Nop(FillerInstruction),
-    Hlt(FillerInstruction),
-    Lock(FillerInstruction),
-    Cbw(FillerInstruction),
-    Cwd(FillerInstruction),
-    Aaa(FillerInstruction),
-    Aad(AAMDBase),
-    Aam(AAMDBase),
-    Aas(FillerInstruction),
-    Daa(FillerInstruction),
-    Das(FillerInstruction),
-    Clc(FillerInstruction),
-    Cld(FillerInstruction),
-    Cli(FillerInstruction),
-    Stc(FillerInstruction),
-    Std(FillerInstruction),
-    Sti(FillerInstruction),
-    Cmc(FillerInstruction),
-    Sahf(FillerInstruction),
-    Lahf(FillerInstruction),
-    Pushf(FillerInstruction),
-    Popf(FillerInstruction),
-    Iret(FillerInstruction),
-    Wait(FillerInstruction),
-    Xlat(FillerInstruction),
-    Ret(RetInstruction),
-    Int(IntInstruction),
-    Out(OutInstruction),
-    In(InInstruction),
-    Rep(RepInstruction),
-    Jcond(JumpInstruction),
-    Jcxz(JcxzInstruction),
-    Seg(SegmentOverride),
-    Loop(LoopInstruction),
-    LoadPointer(LoadInstruction),
+    fn undocumented(bytes: &[u8]) -> bool {
+        decode_bytes(bytes, 0x100).unwrap().is_undocumented()
+    }
+
+    #[test]
+    fn marks_undocumented_opcodes() {
+        assert!(undocumented(&[0x64, 0x05]));
+        assert!(undocumented(&[0xC0, 0x02, 0x00]));
+        assert!(undocumented(&[0xC9]));
+        assert!(undocumented(&[0x0F]));
+        assert!(undocumented(&[0xD6]));
+        assert!(undocumented(&[0xD0, 0xF0]));
+        assert!(undocumented(&[0xF1, 0xA4]));
+        // TEST as F6h/F7h /1
+        assert!(undocumented(&[0xF6, 0xC8, 0x12]));
+        assert!(undocumented(&[0xF7, 0x0F, 0x34, 0x12]));
+        // MOV as C6h/C7h with REG other than 0
+        assert!(undocumented(&[0xC6, 0xC8, 0x12]));
+        assert!(undocumented(&[0xC7, 0x3F, 0x34, 0x12]));
+    }
+
+    #[test]
+    fn leaves_documented_opcodes_alone() {
+        assert!(!undocumented(&[0x74, 0x05]));
+        assert!(!undocumented(&[0xC3]));
+        assert!(!undocumented(&[0x1F]));
+        assert!(!undocumented(&[0xD0, 0xE0]));
+        assert!(!undocumented(&[0xF0, 0xA4]));
+        assert!(!undocumented(&[0xF6, 0xC0, 0x12]));
+        assert!(!undocumented(&[0xF6, 0xD0]));
+        assert!(!undocumented(&[0xC6, 0xC0, 0x12]));
+        assert!(!undocumented(&[0xC7, 0x07, 0x34, 0x12]));
+    }
 }
@@ -1,3 +1,11 @@
+pub mod analysis;
+pub mod cfg;
 pub mod cpu;
 pub mod decoder;
+pub mod disasm;
+pub mod effects;
+pub mod encoder;
+pub mod format;
 pub mod instruction;
+pub mod opcodes;
+pub mod symbols;
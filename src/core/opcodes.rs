@@ -0,0 +1,656 @@
+use crate::core::cpu::Flags;
+use crate::core::instruction::{Instruction, Memory, Mnemonic, Operand, Width};
+
+use Mnemonic::*;
+use OperandEncoding::*;
+
+// Where an opcode's explicit operands come from, in (destination, source) order.
+// E = ModRM R/M, G = ModRM REG, the rest is named after the operand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperandEncoding {
+    Implied,  // No explicit operands
+    RmReg,    // E, G
+    RegRm,    // G, E
+    RmSreg,   // E, sreg in REG
+    SregRm,   // sreg in REG, E
+    RegMem,   // G, E where E must be memory (LEA, LDS, LES)
+    RmImm,    // E, imm of the operand width
+    RmImm8,   // E, imm8 sign-extended to 16 bits
+    Rm,       // E
+    Mem,      // E, must be memory (far indirect CALL/JMP)
+    RmOne,    // E, 1
+    RmCl,     // E, CL
+    AccImm,   // AL/AX, imm
+    RegImm,   // reg in the low 3 opcode bits, imm
+    Reg,      // reg16 in the low 3 opcode bits
+    AccReg,   // AX, reg16 in the low 3 opcode bits
+    Sreg,     // sreg in opcode bits 3-4
+    AccMoffs, // AL/AX, [addr16]
+    MoffsAcc, // [addr16], AL/AX
+    Rel8,     // Short relative target
+    Rel16,    // Near relative target
+    FarPtr,   // offset16, segment16
+    Imm8,     // imm8
+    Imm16,    // imm16
+    AccPort,  // AL/AX, imm8 port
+    PortAcc,  // imm8 port, AL/AX
+    AccDx,    // AL/AX, DX
+    DxAcc,    // DX, AL/AX
+}
+
+impl OperandEncoding {
+    pub fn has_modrm(&self) -> bool {
+        matches!(
+            self,
+            RmReg | RegRm | RmSreg | SregRm | RegMem | RmImm | RmImm8 | Rm | Mem | RmOne | RmCl
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FlagEffects {
+    pub read: Flags,
+    pub written: Flags,
+    pub undefined: Flags,
+}
+
+// Base clock counts from the 8086 datasheet. `base` is the form without a
+// ModRM memory operand, `mem` the form with one, to which the effective
+// address time is added. 0 where the form can't occur. Branches count the
+// taken case, string operations a single iteration. Nothing counts clocks
+// yet.
+#[derive(Debug, Clone, Copy)]
+#[allow(dead_code)]
+pub struct Cycles {
+    pub base: u8,
+    pub mem: u8,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct OpcodeInfo {
+    pub mnemonic: Mnemonic,
+    pub encoding: OperandEncoding,
+    // Operand width, None when there are no operands
+    pub width: Option<Width>,
+    pub flags: FlagEffects,
+    #[allow(dead_code)]
+    pub cycles: Cycles,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Opcode {
+    Op(OpcodeInfo),
+    // Operation selected by the ModRM REG field, None where it is undefined
+    Group(&'static [Option<OpcodeInfo>; 8]),
+    // 8087 escape, decoded from its own tables in decoder::fpu
+    Esc,
+    Prefix,
+}
+
+const B: Option<Width> = Some(Width::Byte);
+const W: Option<Width> = Some(Width::Word);
+const D: Option<Width> = Some(Width::Dword);
+const N: Option<Width> = None;
+
+const O: Flags = Flags::OVERFLOW;
+const S: Flags = Flags::SIGN;
+const Z: Flags = Flags::ZERO;
+const A: Flags = Flags::AUXILIARY_CARRY;
+const P: Flags = Flags::PARITY;
+const C: Flags = Flags::CARRY;
+const NONE: Flags = Flags::empty();
+const SZP: Flags = S.union(Z).union(P);
+const SZAPC: Flags = SZP.union(A).union(C);
+const OSZAPC: Flags = SZAPC.union(O);
+const OSZPC: Flags = SZP.union(O).union(C);
+const ALL: Flags = Flags::all();
+const IT: Flags = Flags::INTERRUPT.union(Flags::TRAP);
+
+const fn fx(read: Flags, written: Flags, undefined: Flags) -> FlagEffects {
+    FlagEffects {
+        read,
+        written,
+        undefined,
+    }
+}
+
+const NO_FLAGS: FlagEffects = fx(NONE, NONE, NONE);
+const ARITH: FlagEffects = fx(NONE, OSZAPC, NONE);
+const ARITH_CARRY: FlagEffects = fx(C, OSZAPC, NONE);
+const LOGIC: FlagEffects = fx(NONE, OSZPC, A);
+const INC_DEC: FlagEffects = fx(NONE, OSZAPC.difference(C), NONE);
+const ROTATE_1: FlagEffects = fx(NONE, O.union(C), NONE);
+const ROTATE_CL: FlagEffects = fx(NONE, C, O);
+const ROTATE_CARRY_1: FlagEffects = fx(C, O.union(C), NONE);
+const ROTATE_CARRY_CL: FlagEffects = fx(C, C, O);
+const SHIFT_1: FlagEffects = fx(NONE, OSZPC, A);
+const SHIFT_CL: FlagEffects = fx(NONE, SZP.union(C), O.union(A));
+const MULTIPLY: FlagEffects = fx(NONE, O.union(C), SZP.union(A));
+const DIVIDE: FlagEffects = fx(NONE, NONE, OSZAPC);
+const DECIMAL_ADJUST: FlagEffects = fx(A.union(C), SZAPC, O);
+const ASCII_ADJUST: FlagEffects = fx(A, A.union(C), O.union(SZP));
+const ASCII_MUL_DIV: FlagEffects = fx(NONE, SZP, O.union(A).union(C));
+const STRING: FlagEffects = fx(Flags::DIRECTION, NONE, NONE);
+const STRING_CMP: FlagEffects = fx(Flags::DIRECTION, OSZAPC, NONE);
+const STORE_AH: FlagEffects = fx(NONE, SZAPC, NONE);
+const LOAD_AH: FlagEffects = fx(SZAPC, NONE, NONE);
+const READ_ALL: FlagEffects = fx(ALL, NONE, NONE);
+const WRITE_ALL: FlagEffects = fx(NONE, ALL, NONE);
+// INT pushes FLAGS before clearing IF and TF
+const INTERRUPT: FlagEffects = fx(ALL, IT, NONE);
+const WRITE_C: FlagEffects = fx(NONE, C, NONE);
+const FLIP_C: FlagEffects = fx(C, C, NONE);
+const WRITE_D: FlagEffects = fx(NONE, Flags::DIRECTION, NONE);
+const WRITE_I: FlagEffects = fx(NONE, Flags::INTERRUPT, NONE);
+const READ_O: FlagEffects = fx(O, NONE, NONE);
+const READ_C: FlagEffects = fx(C, NONE, NONE);
+const READ_Z: FlagEffects = fx(Z, NONE, NONE);
+const READ_CZ: FlagEffects = fx(C.union(Z), NONE, NONE);
+const READ_S: FlagEffects = fx(S, NONE, NONE);
+const READ_P: FlagEffects = fx(P, NONE, NONE);
+const READ_SO: FlagEffects = fx(S.union(O), NONE, NONE);
+const READ_ZSO: FlagEffects = fx(Z.union(S).union(O), NONE, NONE);
+
+const fn info(
+    mnemonic: Mnemonic,
+    encoding: OperandEncoding,
+    width: Option<Width>,
+    flags: FlagEffects,
+    base: u8,
+    mem: u8,
+) -> OpcodeInfo {
+    OpcodeInfo {
+        mnemonic,
+        encoding,
+        width,
+        flags,
+        cycles: Cycles { base, mem },
+    }
+}
+
+const fn op(
+    mnemonic: Mnemonic,
+    encoding: OperandEncoding,
+    width: Option<Width>,
+    flags: FlagEffects,
+    base: u8,
+    mem: u8,
+) -> Opcode {
+    Opcode::Op(info(mnemonic, encoding, width, flags, base, mem))
+}
+
+const fn some(
+    mnemonic: Mnemonic,
+    encoding: OperandEncoding,
+    width: Option<Width>,
+    flags: FlagEffects,
+    base: u8,
+    mem: u8,
+) -> Option<OpcodeInfo> {
+    Some(info(mnemonic, encoding, width, flags, base, mem))
+}
+
+#[rustfmt::skip]
+pub static OPCODES: [Opcode; 256] = [
+    /* 00 */ op(Add, RmReg, B, ARITH, 3, 16),
+    /* 01 */ op(Add, RmReg, W, ARITH, 3, 16),
+    /* 02 */ op(Add, RegRm, B, ARITH, 3, 9),
+    /* 03 */ op(Add, RegRm, W, ARITH, 3, 9),
+    /* 04 */ op(Add, AccImm, B, ARITH, 4, 0),
+    /* 05 */ op(Add, AccImm, W, ARITH, 4, 0),
+    /* 06 */ op(Push, Sreg, W, NO_FLAGS, 10, 0),
+    /* 07 */ op(Pop, Sreg, W, NO_FLAGS, 8, 0),
+    /* 08 */ op(Or, RmReg, B, LOGIC, 3, 16),
+    /* 09 */ op(Or, RmReg, W, LOGIC, 3, 16),
+    /* 0A */ op(Or, RegRm, B, LOGIC, 3, 9),
+    /* 0B */ op(Or, RegRm, W, LOGIC, 3, 9),
+    /* 0C */ op(Or, AccImm, B, LOGIC, 4, 0),
+    /* 0D */ op(Or, AccImm, W, LOGIC, 4, 0),
+    /* 0E */ op(Push, Sreg, W, NO_FLAGS, 10, 0),
+    /* 0F */ op(Pop, Sreg, W, NO_FLAGS, 8, 0), // POP CS, undocumented
+    /* 10 */ op(Adc, RmReg, B, ARITH_CARRY, 3, 16),
+    /* 11 */ op(Adc, RmReg, W, ARITH_CARRY, 3, 16),
+    /* 12 */ op(Adc, RegRm, B, ARITH_CARRY, 3, 9),
+    /* 13 */ op(Adc, RegRm, W, ARITH_CARRY, 3, 9),
+    /* 14 */ op(Adc, AccImm, B, ARITH_CARRY, 4, 0),
+    /* 15 */ op(Adc, AccImm, W, ARITH_CARRY, 4, 0),
+    /* 16 */ op(Push, Sreg, W, NO_FLAGS, 10, 0),
+    /* 17 */ op(Pop, Sreg, W, NO_FLAGS, 8, 0),
+    /* 18 */ op(Sbb, RmReg, B, ARITH_CARRY, 3, 16),
+    /* 19 */ op(Sbb, RmReg, W, ARITH_CARRY, 3, 16),
+    /* 1A */ op(Sbb, RegRm, B, ARITH_CARRY, 3, 9),
+    /* 1B */ op(Sbb, RegRm, W, ARITH_CARRY, 3, 9),
+    /* 1C */ op(Sbb, AccImm, B, ARITH_CARRY, 4, 0),
+    /* 1D */ op(Sbb, AccImm, W, ARITH_CARRY, 4, 0),
+    /* 1E */ op(Push, Sreg, W, NO_FLAGS, 10, 0),
+    /* 1F */ op(Pop, Sreg, W, NO_FLAGS, 8, 0),
+    /* 20 */ op(And, RmReg, B, LOGIC, 3, 16),
+    /* 21 */ op(And, RmReg, W, LOGIC, 3, 16),
+    /* 22 */ op(And, RegRm, B, LOGIC, 3, 9),
+    /* 23 */ op(And, RegRm, W, LOGIC, 3, 9),
+    /* 24 */ op(And, AccImm, B, LOGIC, 4, 0),
+    /* 25 */ op(And, AccImm, W, LOGIC, 4, 0),
+    /* 26 */ Opcode::Prefix, // ES:
+    /* 27 */ op(Daa, Implied, N, DECIMAL_ADJUST, 4, 0),
+    /* 28 */ op(Sub, RmReg, B, ARITH, 3, 16),
+    /* 29 */ op(Sub, RmReg, W, ARITH, 3, 16),
+    /* 2A */ op(Sub, RegRm, B, ARITH, 3, 9),
+    /* 2B */ op(Sub, RegRm, W, ARITH, 3, 9),
+    /* 2C */ op(Sub, AccImm, B, ARITH, 4, 0),
+    /* 2D */ op(Sub, AccImm, W, ARITH, 4, 0),
+    /* 2E */ Opcode::Prefix, // CS:
+    /* 2F */ op(Das, Implied, N, DECIMAL_ADJUST, 4, 0),
+    /* 30 */ op(Xor, RmReg, B, LOGIC, 3, 16),
+    /* 31 */ op(Xor, RmReg, W, LOGIC, 3, 16),
+    /* 32 */ op(Xor, RegRm, B, LOGIC, 3, 9),
+    /* 33 */ op(Xor, RegRm, W, LOGIC, 3, 9),
+    /* 34 */ op(Xor, AccImm, B, LOGIC, 4, 0),
+    /* 35 */ op(Xor, AccImm, W, LOGIC, 4, 0),
+    /* 36 */ Opcode::Prefix, // SS:
+    /* 37 */ op(Aaa, Implied, N, ASCII_ADJUST, 4, 0),
+    /* 38 */ op(Cmp, RmReg, B, ARITH, 3, 9),
+    /* 39 */ op(Cmp, RmReg, W, ARITH, 3, 9),
+    /* 3A */ op(Cmp, RegRm, B, ARITH, 3, 9),
+    /* 3B */ op(Cmp, RegRm, W, ARITH, 3, 9),
+    /* 3C */ op(Cmp, AccImm, B, ARITH, 4, 0),
+    /* 3D */ op(Cmp, AccImm, W, ARITH, 4, 0),
+    /* 3E */ Opcode::Prefix, // DS:
+    /* 3F */ op(Aas, Implied, N, ASCII_ADJUST, 4, 0),
+    /* 40 */ op(Inc, Reg, W, INC_DEC, 2, 0),
+    /* 41 */ op(Inc, Reg, W, INC_DEC, 2, 0),
+    /* 42 */ op(Inc, Reg, W, INC_DEC, 2, 0),
+    /* 43 */ op(Inc, Reg, W, INC_DEC, 2, 0),
+    /* 44 */ op(Inc, Reg, W, INC_DEC, 2, 0),
+    /* 45 */ op(Inc, Reg, W, INC_DEC, 2, 0),
+    /* 46 */ op(Inc, Reg, W, INC_DEC, 2, 0),
+    /* 47 */ op(Inc, Reg, W, INC_DEC, 2, 0),
+    /* 48 */ op(Dec, Reg, W, INC_DEC, 2, 0),
+    /* 49 */ op(Dec, Reg, W, INC_DEC, 2, 0),
+    /* 4A */ op(Dec, Reg, W, INC_DEC, 2, 0),
+    /* 4B */ op(Dec, Reg, W, INC_DEC, 2, 0),
+    /* 4C */ op(Dec, Reg, W, INC_DEC, 2, 0),
+    /* 4D */ op(Dec, Reg, W, INC_DEC, 2, 0),
+    /* 4E */ op(Dec, Reg, W, INC_DEC, 2, 0),
+    /* 4F */ op(Dec, Reg, W, INC_DEC, 2, 0),
+    /* 50 */ op(Push, Reg, W, NO_FLAGS, 11, 0),
+    /* 51 */ op(Push, Reg, W, NO_FLAGS, 11, 0),
+    /* 52 */ op(Push, Reg, W, NO_FLAGS, 11, 0),
+    /* 53 */ op(Push, Reg, W, NO_FLAGS, 11, 0),
+    /* 54 */ op(Push, Reg, W, NO_FLAGS, 11, 0),
+    /* 55 */ op(Push, Reg, W, NO_FLAGS, 11, 0),
+    /* 56 */ op(Push, Reg, W, NO_FLAGS, 11, 0),
+    /* 57 */ op(Push, Reg, W, NO_FLAGS, 11, 0),
+    /* 58 */ op(Pop, Reg, W, NO_FLAGS, 8, 0),
+    /* 59 */ op(Pop, Reg, W, NO_FLAGS, 8, 0),
+    /* 5A */ op(Pop, Reg, W, NO_FLAGS, 8, 0),
+    /* 5B */ op(Pop, Reg, W, NO_FLAGS, 8, 0),
+    /* 5C */ op(Pop, Reg, W, NO_FLAGS, 8, 0),
+    /* 5D */ op(Pop, Reg, W, NO_FLAGS, 8, 0),
+    /* 5E */ op(Pop, Reg, W, NO_FLAGS, 8, 0),
+    /* 5F */ op(Pop, Reg, W, NO_FLAGS, 8, 0),
+    // 60h-6Fh: the 8086 ignores bit 4, so these are undocumented copies of 70h-7Fh
+    /* 60 */ op(Jo, Rel8, N, READ_O, 16, 0),
+    /* 61 */ op(Jno, Rel8, N, READ_O, 16, 0),
+    /* 62 */ op(Jb, Rel8, N, READ_C, 16, 0),
+    /* 63 */ op(Jae, Rel8, N, READ_C, 16, 0),
+    /* 64 */ op(Je, Rel8, N, READ_Z, 16, 0),
+    /* 65 */ op(Jne, Rel8, N, READ_Z, 16, 0),
+    /* 66 */ op(Jbe, Rel8, N, READ_CZ, 16, 0),
+    /* 67 */ op(Ja, Rel8, N, READ_CZ, 16, 0),
+    /* 68 */ op(Js, Rel8, N, READ_S, 16, 0),
+    /* 69 */ op(Jns, Rel8, N, READ_S, 16, 0),
+    /* 6A */ op(Jp, Rel8, N, READ_P, 16, 0),
+    /* 6B */ op(Jnp, Rel8, N, READ_P, 16, 0),
+    /* 6C */ op(Jl, Rel8, N, READ_SO, 16, 0),
+    /* 6D */ op(Jge, Rel8, N, READ_SO, 16, 0),
+    /* 6E */ op(Jle, Rel8, N, READ_ZSO, 16, 0),
+    /* 6F */ op(Jg, Rel8, N, READ_ZSO, 16, 0),
+    /* 70 */ op(Jo, Rel8, N, READ_O, 16, 0),
+    /* 71 */ op(Jno, Rel8, N, READ_O, 16, 0),
+    /* 72 */ op(Jb, Rel8, N, READ_C, 16, 0),
+    /* 73 */ op(Jae, Rel8, N, READ_C, 16, 0),
+    /* 74 */ op(Je, Rel8, N, READ_Z, 16, 0),
+    /* 75 */ op(Jne, Rel8, N, READ_Z, 16, 0),
+    /* 76 */ op(Jbe, Rel8, N, READ_CZ, 16, 0),
+    /* 77 */ op(Ja, Rel8, N, READ_CZ, 16, 0),
+    /* 78 */ op(Js, Rel8, N, READ_S, 16, 0),
+    /* 79 */ op(Jns, Rel8, N, READ_S, 16, 0),
+    /* 7A */ op(Jp, Rel8, N, READ_P, 16, 0),
+    /* 7B */ op(Jnp, Rel8, N, READ_P, 16, 0),
+    /* 7C */ op(Jl, Rel8, N, READ_SO, 16, 0),
+    /* 7D */ op(Jge, Rel8, N, READ_SO, 16, 0),
+    /* 7E */ op(Jle, Rel8, N, READ_ZSO, 16, 0),
+    /* 7F */ op(Jg, Rel8, N, READ_ZSO, 16, 0),
+    /* 80 */ Opcode::Group(&GROUP_80),
+    /* 81 */ Opcode::Group(&GROUP_81),
+    /* 82 */ Opcode::Group(&GROUP_80), // Same as 80h
+    /* 83 */ Opcode::Group(&GROUP_83),
+    /* 84 */ op(Test, RmReg, B, LOGIC, 3, 9),
+    /* 85 */ op(Test, RmReg, W, LOGIC, 3, 9),
+    /* 86 */ op(Xchg, RmReg, B, NO_FLAGS, 4, 17),
+    /* 87 */ op(Xchg, RmReg, W, NO_FLAGS, 4, 17),
+    /* 88 */ op(Mov, RmReg, B, NO_FLAGS, 2, 9),
+    /* 89 */ op(Mov, RmReg, W, NO_FLAGS, 2, 9),
+    /* 8A */ op(Mov, RegRm, B, NO_FLAGS, 2, 8),
+    /* 8B */ op(Mov, RegRm, W, NO_FLAGS, 2, 8),
+    /* 8C */ op(Mov, RmSreg, W, NO_FLAGS, 2, 9),
+    /* 8D */ op(Lea, RegMem, W, NO_FLAGS, 0, 2),
+    /* 8E */ op(Mov, SregRm, W, NO_FLAGS, 2, 8),
+    /* 8F */ Opcode::Group(&GROUP_8F),
+    /* 90 */ op(Xchg, AccReg, W, NO_FLAGS, 3, 0), // NOP
+    /* 91 */ op(Xchg, AccReg, W, NO_FLAGS, 3, 0),
+    /* 92 */ op(Xchg, AccReg, W, NO_FLAGS, 3, 0),
+    /* 93 */ op(Xchg, AccReg, W, NO_FLAGS, 3, 0),
+    /* 94 */ op(Xchg, AccReg, W, NO_FLAGS, 3, 0),
+    /* 95 */ op(Xchg, AccReg, W, NO_FLAGS, 3, 0),
+    /* 96 */ op(Xchg, AccReg, W, NO_FLAGS, 3, 0),
+    /* 97 */ op(Xchg, AccReg, W, NO_FLAGS, 3, 0),
+    /* 98 */ op(Cbw, Implied, N, NO_FLAGS, 2, 0),
+    /* 99 */ op(Cwd, Implied, N, NO_FLAGS, 5, 0),
+    /* 9A */ op(Call, FarPtr, N, NO_FLAGS, 28, 0),
+    /* 9B */ op(Wait, Implied, N, NO_FLAGS, 3, 0),
+    /* 9C */ op(Pushf, Implied, N, READ_ALL, 10, 0),
+    /* 9D */ op(Popf, Implied, N, WRITE_ALL, 8, 0),
+    /* 9E */ op(Sahf, Implied, N, STORE_AH, 4, 0),
+    /* 9F */ op(Lahf, Implied, N, LOAD_AH, 4, 0),
+    /* A0 */ op(Mov, AccMoffs, B, NO_FLAGS, 10, 0),
+    /* A1 */ op(Mov, AccMoffs, W, NO_FLAGS, 10, 0),
+    /* A2 */ op(Mov, MoffsAcc, B, NO_FLAGS, 10, 0),
+    /* A3 */ op(Mov, MoffsAcc, W, NO_FLAGS, 10, 0),
+    /* A4 */ op(Movsb, Implied, B, STRING, 18, 0),
+    /* A5 */ op(Movsw, Implied, W, STRING, 18, 0),
+    /* A6 */ op(Cmpsb, Implied, B, STRING_CMP, 22, 0),
+    /* A7 */ op(Cmpsw, Implied, W, STRING_CMP, 22, 0),
+    /* A8 */ op(Test, AccImm, B, LOGIC, 4, 0),
+    /* A9 */ op(Test, AccImm, W, LOGIC, 4, 0),
+    /* AA */ op(Stosb, Implied, B, STRING, 11, 0),
+    /* AB */ op(Stosw, Implied, W, STRING, 11, 0),
+    /* AC */ op(Lodsb, Implied, B, STRING, 12, 0),
+    /* AD */ op(Lodsw, Implied, W, STRING, 12, 0),
+    /* AE */ op(Scasb, Implied, B, STRING_CMP, 15, 0),
+    /* AF */ op(Scasw, Implied, W, STRING_CMP, 15, 0),
+    /* B0 */ op(Mov, RegImm, B, NO_FLAGS, 4, 0),
+    /* B1 */ op(Mov, RegImm, B, NO_FLAGS, 4, 0),
+    /* B2 */ op(Mov, RegImm, B, NO_FLAGS, 4, 0),
+    /* B3 */ op(Mov, RegImm, B, NO_FLAGS, 4, 0),
+    /* B4 */ op(Mov, RegImm, B, NO_FLAGS, 4, 0),
+    /* B5 */ op(Mov, RegImm, B, NO_FLAGS, 4, 0),
+    /* B6 */ op(Mov, RegImm, B, NO_FLAGS, 4, 0),
+    /* B7 */ op(Mov, RegImm, B, NO_FLAGS, 4, 0),
+    /* B8 */ op(Mov, RegImm, W, NO_FLAGS, 4, 0),
+    /* B9 */ op(Mov, RegImm, W, NO_FLAGS, 4, 0),
+    /* BA */ op(Mov, RegImm, W, NO_FLAGS, 4, 0),
+    /* BB */ op(Mov, RegImm, W, NO_FLAGS, 4, 0),
+    /* BC */ op(Mov, RegImm, W, NO_FLAGS, 4, 0),
+    /* BD */ op(Mov, RegImm, W, NO_FLAGS, 4, 0),
+    /* BE */ op(Mov, RegImm, W, NO_FLAGS, 4, 0),
+    /* BF */ op(Mov, RegImm, W, NO_FLAGS, 4, 0),
+    // C0h/C1h and C8h/C9h: the 8086 ignores bit 1, undocumented copies of the RET forms
+    /* C0 */ op(Ret, Imm16, N, NO_FLAGS, 12, 0),
+    /* C1 */ op(Ret, Implied, N, NO_FLAGS, 8, 0),
+    /* C2 */ op(Ret, Imm16, N, NO_FLAGS, 12, 0),
+    /* C3 */ op(Ret, Implied, N, NO_FLAGS, 8, 0),
+    /* C4 */ op(Les, RegMem, D, NO_FLAGS, 0, 16),
+    /* C5 */ op(Lds, RegMem, D, NO_FLAGS, 0, 16),
+    // The 8086 ignores the REG field of C6h/C7h
+    /* C6 */ op(Mov, RmImm, B, NO_FLAGS, 4, 10),
+    /* C7 */ op(Mov, RmImm, W, NO_FLAGS, 4, 10),
+    /* C8 */ op(Retf, Imm16, N, NO_FLAGS, 17, 0),
+    /* C9 */ op(Retf, Implied, N, NO_FLAGS, 18, 0),
+    /* CA */ op(Retf, Imm16, N, NO_FLAGS, 17, 0),
+    /* CB */ op(Retf, Implied, N, NO_FLAGS, 18, 0),
+    /* CC */ op(Int3, Implied, N, INTERRUPT, 52, 0),
+    /* CD */ op(Int, Imm8, N, INTERRUPT, 51, 0),
+    /* CE */ op(Into, Implied, N, INTERRUPT, 53, 0),
+    /* CF */ op(Iret, Implied, N, WRITE_ALL, 24, 0),
+    /* D0 */ Opcode::Group(&GROUP_D0),
+    /* D1 */ Opcode::Group(&GROUP_D1),
+    /* D2 */ Opcode::Group(&GROUP_D2),
+    /* D3 */ Opcode::Group(&GROUP_D3),
+    /* D4 */ op(Aam, Imm8, N, ASCII_MUL_DIV, 83, 0),
+    /* D5 */ op(Aad, Imm8, N, ASCII_MUL_DIV, 60, 0),
+    /* D6 */ op(Salc, Implied, N, READ_C, 3, 0), // Undocumented
+    /* D7 */ op(Xlatb, Implied, N, NO_FLAGS, 11, 0),
+    /* D8 */ Opcode::Esc,
+    /* D9 */ Opcode::Esc,
+    /* DA */ Opcode::Esc,
+    /* DB */ Opcode::Esc,
+    /* DC */ Opcode::Esc,
+    /* DD */ Opcode::Esc,
+    /* DE */ Opcode::Esc,
+    /* DF */ Opcode::Esc,
+    /* E0 */ op(Loopnz, Rel8, N, READ_Z, 19, 0),
+    /* E1 */ op(Loopz, Rel8, N, READ_Z, 18, 0),
+    /* E2 */ op(Loop, Rel8, N, NO_FLAGS, 17, 0),
+    /* E3 */ op(Jcxz, Rel8, N, NO_FLAGS, 18, 0),
+    /* E4 */ op(In, AccPort, B, NO_FLAGS, 10, 0),
+    /* E5 */ op(In, AccPort, W, NO_FLAGS, 10, 0),
+    /* E6 */ op(Out, PortAcc, B, NO_FLAGS, 10, 0),
+    /* E7 */ op(Out, PortAcc, W, NO_FLAGS, 10, 0),
+    /* E8 */ op(Call, Rel16, N, NO_FLAGS, 19, 0),
+    /* E9 */ op(Jmp, Rel16, N, NO_FLAGS, 15, 0),
+    /* EA */ op(Jmp, FarPtr, N, NO_FLAGS, 15, 0),
+    /* EB */ op(Jmp, Rel8, N, NO_FLAGS, 15, 0),
+    /* EC */ op(In, AccDx, B, NO_FLAGS, 8, 0),
+    /* ED */ op(In, AccDx, W, NO_FLAGS, 8, 0),
+    /* EE */ op(Out, DxAcc, B, NO_FLAGS, 8, 0),
+    /* EF */ op(Out, DxAcc, W, NO_FLAGS, 8, 0),
+    /* F0 */ Opcode::Prefix, // LOCK
+    /* F1 */ Opcode::Prefix, // LOCK, undocumented alias
+    /* F2 */ Opcode::Prefix, // REPNZ
+    /* F3 */ Opcode::Prefix, // REPZ
+    /* F4 */ op(Hlt, Implied, N, NO_FLAGS, 2, 0),
+    /* F5 */ op(Cmc, Implied, N, FLIP_C, 2, 0),
+    /* F6 */ Opcode::Group(&GROUP_F6),
+    /* F7 */ Opcode::Group(&GROUP_F7),
+    /* F8 */ op(Clc, Implied, N, WRITE_C, 2, 0),
+    /* F9 */ op(Stc, Implied, N, WRITE_C, 2, 0),
+    /* FA */ op(Cli, Implied, N, WRITE_I, 2, 0),
+    /* FB */ op(Sti, Implied, N, WRITE_I, 2, 0),
+    /* FC */ op(Cld, Implied, N, WRITE_D, 2, 0),
+    /* FD */ op(Std, Implied, N, WRITE_D, 2, 0),
+    /* FE */ Opcode::Group(&GROUP_FE),
+    /* FF */ Opcode::Group(&GROUP_FF),
+];
+
+#[rustfmt::skip]
+pub static GROUP_80: [Option<OpcodeInfo>; 8] = [
+    /* /0 */ some(Add, RmImm, B, ARITH, 4, 17),
+    /* /1 */ some(Or, RmImm, B, LOGIC, 4, 17),
+    /* /2 */ some(Adc, RmImm, B, ARITH_CARRY, 4, 17),
+    /* /3 */ some(Sbb, RmImm, B, ARITH_CARRY, 4, 17),
+    /* /4 */ some(And, RmImm, B, LOGIC, 4, 17),
+    /* /5 */ some(Sub, RmImm, B, ARITH, 4, 17),
+    /* /6 */ some(Xor, RmImm, B, LOGIC, 4, 17),
+    /* /7 */ some(Cmp, RmImm, B, ARITH, 4, 10),
+];
+
+#[rustfmt::skip]
+pub static GROUP_81: [Option<OpcodeInfo>; 8] = [
+    /* /0 */ some(Add, RmImm, W, ARITH, 4, 17),
+    /* /1 */ some(Or, RmImm, W, LOGIC, 4, 17),
+    /* /2 */ some(Adc, RmImm, W, ARITH_CARRY, 4, 17),
+    /* /3 */ some(Sbb, RmImm, W, ARITH_CARRY, 4, 17),
+    /* /4 */ some(And, RmImm, W, LOGIC, 4, 17),
+    /* /5 */ some(Sub, RmImm, W, ARITH, 4, 17),
+    /* /6 */ some(Xor, RmImm, W, LOGIC, 4, 17),
+    /* /7 */ some(Cmp, RmImm, W, ARITH, 4, 10),
+];
+
+#[rustfmt::skip]
+pub static GROUP_83: [Option<OpcodeInfo>; 8] = [
+    /* /0 */ some(Add, RmImm8, W, ARITH, 4, 17),
+    /* /1 */ some(Or, RmImm8, W, LOGIC, 4, 17),
+    /* /2 */ some(Adc, RmImm8, W, ARITH_CARRY, 4, 17),
+    /* /3 */ some(Sbb, RmImm8, W, ARITH_CARRY, 4, 17),
+    /* /4 */ some(And, RmImm8, W, LOGIC, 4, 17),
+    /* /5 */ some(Sub, RmImm8, W, ARITH, 4, 17),
+    /* /6 */ some(Xor, RmImm8, W, LOGIC, 4, 17),
+    /* /7 */ some(Cmp, RmImm8, W, ARITH, 4, 10),
+];
+
+#[rustfmt::skip]
+pub static GROUP_8F: [Option<OpcodeInfo>; 8] = [
+    /* /0 */ some(Pop, Rm, W, NO_FLAGS, 8, 17),
+    None, None, None, None, None, None, None,
+];
+
+// Shift and rotate by 1. /6 is the undocumented SETMO.
+#[rustfmt::skip]
+pub static GROUP_D0: [Option<OpcodeInfo>; 8] = [
+    /* /0 */ some(Rol, RmOne, B, ROTATE_1, 2, 15),
+    /* /1 */ some(Ror, RmOne, B, ROTATE_1, 2, 15),
+    /* /2 */ some(Rcl, RmOne, B, ROTATE_CARRY_1, 2, 15),
+    /* /3 */ some(Rcr, RmOne, B, ROTATE_CARRY_1, 2, 15),
+    /* /4 */ some(Shl, RmOne, B, SHIFT_1, 2, 15),
+    /* /5 */ some(Shr, RmOne, B, SHIFT_1, 2, 15),
+    /* /6 */ some(Setmo, RmOne, B, LOGIC, 2, 15),
+    /* /7 */ some(Sar, RmOne, B, SHIFT_1, 2, 15),
+];
+
+#[rustfmt::skip]
+pub static GROUP_D1: [Option<OpcodeInfo>; 8] = [
+    /* /0 */ some(Rol, RmOne, W, ROTATE_1, 2, 15),
+    /* /1 */ some(Ror, RmOne, W, ROTATE_1, 2, 15),
+    /* /2 */ some(Rcl, RmOne, W, ROTATE_CARRY_1, 2, 15),
+    /* /3 */ some(Rcr, RmOne, W, ROTATE_CARRY_1, 2, 15),
+    /* /4 */ some(Shl, RmOne, W, SHIFT_1, 2, 15),
+    /* /5 */ some(Shr, RmOne, W, SHIFT_1, 2, 15),
+    /* /6 */ some(Setmo, RmOne, W, LOGIC, 2, 15),
+    /* /7 */ some(Sar, RmOne, W, SHIFT_1, 2, 15),
+];
+
+// Shift and rotate by CL, plus 4 clocks per bit
+#[rustfmt::skip]
+pub static GROUP_D2: [Option<OpcodeInfo>; 8] = [
+    /* /0 */ some(Rol, RmCl, B, ROTATE_CL, 8, 20),
+    /* /1 */ some(Ror, RmCl, B, ROTATE_CL, 8, 20),
+    /* /2 */ some(Rcl, RmCl, B, ROTATE_CARRY_CL, 8, 20),
+    /* /3 */ some(Rcr, RmCl, B, ROTATE_CARRY_CL, 8, 20),
+    /* /4 */ some(Shl, RmCl, B, SHIFT_CL, 8, 20),
+    /* /5 */ some(Shr, RmCl, B, SHIFT_CL, 8, 20),
+    /* /6 */ some(Setmo, RmCl, B, LOGIC, 8, 20),
+    /* /7 */ some(Sar, RmCl, B, SHIFT_CL, 8, 20),
+];
+
+#[rustfmt::skip]
+pub static GROUP_D3: [Option<OpcodeInfo>; 8] = [
+    /* /0 */ some(Rol, RmCl, W, ROTATE_CL, 8, 20),
+    /* /1 */ some(Ror, RmCl, W, ROTATE_CL, 8, 20),
+    /* /2 */ some(Rcl, RmCl, W, ROTATE_CARRY_CL, 8, 20),
+    /* /3 */ some(Rcr, RmCl, W, ROTATE_CARRY_CL, 8, 20),
+    /* /4 */ some(Shl, RmCl, W, SHIFT_CL, 8, 20),
+    /* /5 */ some(Shr, RmCl, W, SHIFT_CL, 8, 20),
+    /* /6 */ some(Setmo, RmCl, W, LOGIC, 8, 20),
+    /* /7 */ some(Sar, RmCl, W, SHIFT_CL, 8, 20),
+];
+
+// The 8086 ignores the low bit of the REG field for TEST, so /1 runs as /0.
+// MUL/DIV counts are the fastest case.
+#[rustfmt::skip]
+pub static GROUP_F6: [Option<OpcodeInfo>; 8] = [
+    /* /0 */ some(Test, RmImm, B, LOGIC, 5, 11),
+    /* /1 */ some(Test, RmImm, B, LOGIC, 5, 11),
+    /* /2 */ some(Not, Rm, B, NO_FLAGS, 3, 16),
+    /* /3 */ some(Neg, Rm, B, ARITH, 3, 16),
+    /* /4 */ some(Mul, Rm, B, MULTIPLY, 70, 76),
+    /* /5 */ some(Imul, Rm, B, MULTIPLY, 80, 86),
+    /* /6 */ some(Div, Rm, B, DIVIDE, 80, 86),
+    /* /7 */ some(Idiv, Rm, B, DIVIDE, 101, 107),
+];
+
+#[rustfmt::skip]
+pub static GROUP_F7: [Option<OpcodeInfo>; 8] = [
+    /* /0 */ some(Test, RmImm, W, LOGIC, 5, 11),
+    /* /1 */ some(Test, RmImm, W, LOGIC, 5, 11),
+    /* /2 */ some(Not, Rm, W, NO_FLAGS, 3, 16),
+    /* /3 */ some(Neg, Rm, W, ARITH, 3, 16),
+    /* /4 */ some(Mul, Rm, W, MULTIPLY, 118, 124),
+    /* /5 */ some(Imul, Rm, W, MULTIPLY, 128, 134),
+    /* /6 */ some(Div, Rm, W, DIVIDE, 144, 150),
+    /* /7 */ some(Idiv, Rm, W, DIVIDE, 165, 171),
+];
+
+#[rustfmt::skip]
+pub static GROUP_FE: [Option<OpcodeInfo>; 8] = [
+    /* /0 */ some(Inc, Rm, B, INC_DEC, 3, 15),
+    /* /1 */ some(Dec, Rm, B, INC_DEC, 3, 15),
+    None, None, None, None, None, None,
+];
+
+// Far targets are an offset:segment dword and can only come from memory
+#[rustfmt::skip]
+pub static GROUP_FF: [Option<OpcodeInfo>; 8] = [
+    /* /0 */ some(Inc, Rm, W, INC_DEC, 3, 15),
+    /* /1 */ some(Dec, Rm, W, INC_DEC, 3, 15),
+    /* /2 */ some(Call, Rm, W, NO_FLAGS, 16, 21),
+    /* /3 */ some(Call, Mem, D, NO_FLAGS, 0, 37),
+    /* /4 */ some(Jmp, Rm, W, NO_FLAGS, 11, 18),
+    /* /5 */ some(Jmp, Mem, D, NO_FLAGS, 0, 24),
+    /* /6 */ some(Push, Rm, W, NO_FLAGS, 11, 16),
+    /* /7 */ None,
+];
+
+// Table entry a decoded instruction came from, see Instruction::info
+pub fn lookup(ins: &Instruction) -> Option<&'static OpcodeInfo> {
+    match &OPCODES[ins.opcode as usize] {
+        Opcode::Op(info) => Some(info),
+        Opcode::Group(group) => {
+            // Near and far indirect CALL/JMP share a mnemonic, the operand width tells them apart
+            let far = matches!(
+                ins.operands[0],
+                Some(Operand::Mem(Memory {
+                    width: Width::Dword,
+                    ..
+                }))
+            );
+            group
+                .iter()
+                .flatten()
+                .find(|info| info.mnemonic == ins.mnemonic && (info.encoding == Mem) == far)
+        }
+        Opcode::Esc | Opcode::Prefix => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::decoder::decode_bytes;
+
+    // Base and memory clocks of the table entry the bytes decode from
+    fn cycles(bytes: &[u8]) -> (u8, u8) {
+        let ins = decode_bytes(bytes, 0x100).unwrap();
+        let cycles = ins.info().unwrap().cycles;
+        (cycles.base, cycles.mem)
+    }
+
+    // Against the 8086 instruction timing table
+    #[test]
+    fn matches_the_datasheet() {
+        let cases: [(&[u8], (u8, u8)); 20] = [
+            (&[0x00, 0xC0], (3, 16)),       // ADD r/m8, reg8
+            (&[0x03, 0xC0], (3, 9)),        // ADD reg16, r/m16
+            (&[0x04, 0x00], (4, 0)),        // ADD AL, imm8
+            (&[0x88, 0xC0], (2, 9)),        // MOV r/m8, reg8
+            (&[0x8B, 0xC0], (2, 8)),        // MOV reg16, r/m16
+            (&[0xB8, 0x00, 0x00], (4, 0)),  // MOV reg16, imm16
+            (&[0x40], (2, 0)),              // INC reg16
+            (&[0xFE, 0xC0], (3, 15)),       // INC r/m8
+            (&[0xFF, 0xC0], (3, 15)),       // INC r/m16
+            (&[0xFF, 0xC8], (3, 15)),       // DEC r/m16
+            (&[0x50], (11, 0)),             // PUSH reg16
+            (&[0x58], (8, 0)),              // POP reg16
+            (&[0xFF, 0xD0], (16, 21)),      // CALL r/m16
+            (&[0xFF, 0x18], (0, 37)),       // CALL far mem
+            (&[0xE8, 0x00, 0x00], (19, 0)), // CALL rel16
+            (&[0xEB, 0x00], (15, 0)),       // JMP rel8
+            (&[0x74, 0x00], (16, 0)),       // JE, taken
+            (&[0xC3], (8, 0)),              // RET
+            (&[0xF6, 0xE0], (70, 76)),      // MUL r/m8
+            (&[0xCD, 0x21], (51, 0)),       // INT imm8
+        ];
+        for (bytes, expected) in cases {
+            assert_eq!(cycles(bytes), expected, "{:02X?}", bytes);
+        }
+    }
+}
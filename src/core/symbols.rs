@@ -0,0 +1,304 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Names for code and data offsets, loaded from linker maps or assembler
+// listings. Addresses are offsets in the program's segment.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    by_address: BTreeMap<u16, String>,
+    by_name: HashMap<String, u16>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Loads a MASM/TLINK .MAP file or a NASM -l listing, told apart by content
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        if text.contains("Publics by") {
+            Ok(Self::parse_map(&text))
+        } else {
+            Ok(Self::parse_listing(&text))
+        }
+    }
+
+    // The "Publics by Name/Value" sections of a linker map:
+    //  0000:0112       main
+    pub fn parse_map(text: &str) -> Self {
+        let mut table = Self::new();
+        let mut in_publics = false;
+        for line in text.lines() {
+            if line.contains("Publics by") {
+                in_publics = true;
+                continue;
+            }
+            if !in_publics {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let Some(address) = fields.next().and_then(parse_segmented) else {
+                continue;
+            };
+            // Absolute symbols are constants rather than addresses. TLINK also
+            // flags unreferenced and resident ones before the name.
+            let mut name = fields.next();
+            if name == Some("Abs") {
+                continue;
+            }
+            if matches!(name, Some("Idle" | "Res")) {
+                name = fields.next();
+            }
+            if let Some(name) = name {
+                table.insert(name, address);
+            }
+        }
+        table
+    }
+
+    // Labels in a NASM listing. Offsets there are relative to the section, so
+    // an ORG in the source is added back. A label on a line of its own takes
+    // the offset of the next line that has one.
+    //      2                                  main:
+    //      3 00000000 B409                        mov ah, 9
+    pub fn parse_listing(text: &str) -> Self {
+        // Source starts after the line number, offset and hex columns
+        const SOURCE_COLUMN: usize = 40;
+
+        let mut table = Self::new();
+        let mut base = 0u16;
+        let mut scope = String::new();
+        let mut waiting: Vec<String> = Vec::new();
+        for line in text.lines() {
+            let address = line
+                .get(7..15)
+                .and_then(|field| u32::from_str_radix(field, 16).ok());
+            let (columns, source) = match line.char_indices().nth(SOURCE_COLUMN) {
+                Some((at, _)) => line.split_at(at),
+                None => (line, ""),
+            };
+            // Lines from macro expansions are marked <1>, <2>, ...
+            let in_macro = columns
+                .match_indices('<')
+                .any(|(at, _)| columns[at + 1..].starts_with(|c: char| c.is_ascii_digit()));
+            let source = source.trim();
+
+            if !in_macro && let Some(origin) = parse_org(source) {
+                base = origin;
+            }
+            if !in_macro && let Some(label) = parse_label(source) {
+                // .name is local to the last non-local label
+                let name = if label.starts_with('.') && !label.starts_with("..") {
+                    format!("{}{}", scope, label)
+                } else {
+                    scope = label.to_string();
+                    label.to_string()
+                };
+                waiting.push(name);
+            }
+            if let Some(offset) = address {
+                for name in waiting.drain(..) {
+                    table.insert(&name, base.wrapping_add(offset as u16));
+                }
+            }
+        }
+        table
+    }
+
+    pub fn insert(&mut self, name: &str, address: u16) {
+        // The first name for an address is the one shown
+        self.by_address
+            .entry(address)
+            .or_insert_with(|| name.to_string());
+        self.by_name.insert(name.to_string(), address);
+    }
+
+    pub fn extend(&mut self, other: SymbolTable) {
+        for (name, address) in other.by_name {
+            self.insert(&name, address);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_name.len()
+    }
+
+    #[allow(dead_code)] // goes with len
+    pub fn is_empty(&self) -> bool {
+        self.by_name.is_empty()
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<u16> {
+        self.by_name.get(name).copied()
+    }
+
+    // Name of exactly this address
+    pub fn name(&self, address: u16) -> Option<&str> {
+        self.by_address.get(&address).map(String::as_str)
+    }
+
+    // `main` or `main+0x12` for the closest symbol at or below the address
+    pub fn symbolize(&self, address: u16) -> Option<String> {
+        let (&start, name) = self.by_address.range(..=address).next_back()?;
+        match address - start {
+            0 => Some(name.clone()),
+            offset => Some(format!("{}+0x{:x}", name, offset)),
+        }
+    }
+
+    // Evaluates `main`, `main+0x12`, `loop-2` or a plain number
+    pub fn resolve(&self, expr: &str) -> Option<u16> {
+        let expr = expr.trim();
+        let split = expr
+            .char_indices()
+            .skip(1)
+            .find(|&(_, c)| c == '+' || c == '-')
+            .map(|(at, _)| at);
+        let (term, offset) = match split {
+            Some(at) => {
+                let offset = parse_number(expr[at + 1..].trim()).ok()?;
+                match &expr[at..at + 1] {
+                    "+" => (expr[..at].trim(), offset),
+                    _ => (expr[..at].trim(), offset.wrapping_neg()),
+                }
+            }
+            None => (expr, 0),
+        };
+        let base = self.lookup(term).or_else(|| parse_number(term).ok())?;
+        Some(base.wrapping_add(offset))
+    }
+}
+
+// Accepts 0x1A3, 1A3h or plain decimal
+pub fn parse_number(text: &str) -> Result<u16, String> {
+    let parsed = if let Some(hex) = text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+        u16::from_str_radix(hex, 16)
+    } else if let Some(hex) = text.strip_suffix('h').or(text.strip_suffix('H')) {
+        u16::from_str_radix(hex, 16)
+    } else {
+        text.parse()
+    };
+    parsed.map_err(|err| format!("invalid number '{}': {}", text, err))
+}
+
+// SSSS:OOOO as an offset from the first segment
+fn parse_segmented(text: &str) -> Option<u16> {
+    let (segment, offset) = text.split_once(':')?;
+    let segment = u16::from_str_radix(segment, 16).ok()?;
+    let offset = u16::from_str_radix(offset, 16).ok()?;
+    Some(segment.wrapping_shl(4).wrapping_add(offset))
+}
+
+// `org 0x100` or `[org 0x100]`
+fn parse_org(source: &str) -> Option<u16> {
+    let source = source.trim_start_matches('[').trim_end_matches(']');
+    let (directive, value) = source.split_once(char::is_whitespace)?;
+    if !directive.eq_ignore_ascii_case("org") {
+        return None;
+    }
+    parse_number(value.split(';').next()?.trim()).ok()
+}
+
+// `name:` at the start of a source line
+fn parse_label(source: &str) -> Option<&str> {
+    let (label, _) = source.split_once(':')?;
+    let valid = label
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "_.?$@#~".contains(c));
+    let starts = label.starts_with(|c: char| c.is_ascii_alphabetic() || "_.?$@".contains(c));
+    (valid && starts).then_some(label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A NASM listing line: number, offset and hex columns, then the source
+    // from SOURCE_COLUMN on
+    fn listing_line(number: u32, offset: Option<u32>, hex: &str, source: &str) -> String {
+        let offset = offset.map(|o| format!("{:08X}", o)).unwrap_or_default();
+        format!("{:6} {:8} {:<24}{}", number, offset, hex, source)
+    }
+
+    #[test]
+    fn reads_labels_from_a_listing() {
+        let text = [
+            listing_line(1, None, "", "org 0x100"),
+            listing_line(2, None, "", "main:"),
+            listing_line(3, Some(0), "B409", "    mov ah, 9"),
+            listing_line(4, Some(2), "40", ".loop: inc ax"),
+            listing_line(5, None, "", "%macro pause 0"),
+            listing_line(6, Some(3), "<1> 90", "inner: nop"),
+            listing_line(7, Some(4), "C3", "done: ret"),
+        ]
+        .join("\n");
+        let table = SymbolTable::parse_listing(&text);
+
+        assert_eq!(table.lookup("main"), Some(0x100));
+        assert_eq!(table.lookup("main.loop"), Some(0x102));
+        assert_eq!(table.lookup("inner"), None);
+        assert_eq!(table.lookup("done"), Some(0x104));
+        assert_eq!(table.len(), 3);
+    }
+
+    #[test]
+    fn reads_publics_from_a_map() {
+        let text = "\
+ Start  Stop   Length Name               Class
+ 00000H 0021FH 00220H _TEXT              CODE
+ 0000:0100       before
+
+  Address         Publics by Value
+
+ 0000:0000  Abs  CONST
+ 0000:0112       main
+ 0010:0004  Idle helper
+ 0000:0200  Res  resident
+";
+        let table = SymbolTable::parse_map(text);
+
+        assert_eq!(table.lookup("before"), None);
+        assert_eq!(table.lookup("CONST"), None);
+        assert_eq!(table.lookup("main"), Some(0x112));
+        assert_eq!(table.lookup("helper"), Some(0x104));
+        assert_eq!(table.lookup("resident"), Some(0x200));
+        assert_eq!(table.len(), 3);
+    }
+
+    #[test]
+    fn resolves_expressions() {
+        let mut table = SymbolTable::new();
+        table.insert("main", 0x112);
+
+        assert_eq!(table.resolve("main"), Some(0x112));
+        assert_eq!(table.resolve("main+0x12"), Some(0x124));
+        assert_eq!(table.resolve(" main - 2 "), Some(0x110));
+        assert_eq!(table.resolve("1A3h"), Some(0x1A3));
+        assert_eq!(table.resolve("0-1"), Some(0xFFFF));
+        assert_eq!(table.resolve("missing"), None);
+        assert_eq!(table.resolve("main+x"), None);
+    }
+
+    #[test]
+    fn symbolizes_the_closest_symbol_below() {
+        let mut table = SymbolTable::new();
+        table.insert("main", 0x112);
+
+        assert_eq!(table.symbolize(0x112).as_deref(), Some("main"));
+        assert_eq!(table.symbolize(0x124).as_deref(), Some("main+0x12"));
+        assert_eq!(table.symbolize(0x100), None);
+    }
+
+    #[test]
+    fn parses_numbers() {
+        assert_eq!(parse_number("0x1A3"), Ok(0x1A3));
+        assert_eq!(parse_number("0X1a3"), Ok(0x1A3));
+        assert_eq!(parse_number("1A3h"), Ok(0x1A3));
+        assert_eq!(parse_number("419"), Ok(419));
+        assert!(parse_number("0x10000").is_err());
+        assert!(parse_number("12g").is_err());
+    }
+}
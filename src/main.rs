@@ -1,7 +1,13 @@
 mod core;
 
+use crate::core::analysis;
+use crate::core::cfg;
 use crate::core::cpu::Cpu;
-use clap::Parser;
+use crate::core::disasm;
+use crate::core::format::Syntax;
+use crate::core::symbols::{self, SymbolTable};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand};
 use rustyline::DefaultEditor;
 use rustyline::error::ReadlineError;
 use std::fs;
@@ -9,17 +15,170 @@ use std::fs;
 /// A simple 8086 emulator CLI
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(arg_required_else_help = true)]
 struct Args {
-    /// Path to the .com program to load
-    program_path: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Path to a .com program to run, short for `run <PROGRAM_PATH>`
+    program_path: Option<String>,
+
+    /// Assembly syntax to print instructions in
+    #[arg(long, global = true, value_enum, default_value_t = Syntax::Intel)]
+    syntax: Syntax,
+
+    /// Symbols to show addresses with, from a .MAP file or a NASM listing.
+    /// May be given more than once.
+    #[arg(long, global = true)]
+    symbols: Vec<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Load a .com program and step through it interactively
+    Run {
+        /// Path to the .com program to load
+        program_path: String,
+    },
+    /// Disassemble a binary file from start to end
+    Disasm {
+        /// Path to the binary to disassemble
+        program_path: String,
+
+        /// Address the first byte is loaded at
+        #[arg(long, value_parser = symbols::parse_number, default_value = "0x100")]
+        origin: u16,
+
+        /// Follow jumps and calls from the entry point instead of sweeping
+        /// linearly, listing what isn't reached as data
+        #[arg(long)]
+        recursive: bool,
+
+        /// Where recursive disassembly starts, an address or symbol. The
+        /// origin by default.
+        #[arg(long)]
+        entry: Option<String>,
+    },
+    /// Write the control-flow graph of a function as Graphviz DOT
+    Cfg {
+        /// Path to the binary to analyse
+        program_path: String,
+
+        /// Address the first byte is loaded at
+        #[arg(long, value_parser = symbols::parse_number, default_value = "0x100")]
+        origin: u16,
+
+        /// Entry of the function, an address or symbol. The origin by default.
+        #[arg(long)]
+        function: Option<String>,
+
+        /// File to write the graph to instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+}
+
+// Address given on the command line, a number or a symbol expression
+fn resolve(symbols: &SymbolTable, expr: Option<&str>, default: u16) -> Result<u16, String> {
+    match expr {
+        Some(expr) => symbols
+            .resolve(expr)
+            .ok_or_else(|| format!("unknown address or symbol '{}'", expr)),
+        None => Ok(default),
+    }
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
+    // A bare program path runs it, as before there were subcommands
+    let command = match (args.command, args.program_path) {
+        (Some(command), None) => command,
+        (Some(_), Some(program_path)) => Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                format!("'{}' can't be given before a subcommand", program_path),
+            )
+            .exit(),
+        (None, Some(program_path)) => Command::Run { program_path },
+        (None, None) => Args::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "a program path or a subcommand is required",
+            )
+            .exit(),
+    };
+    let mut symbols = SymbolTable::new();
+    for path in &args.symbols {
+        symbols.extend(SymbolTable::load(path)?);
+    }
+    // Symbol at the address, else a generated label, else symbol+offset
+    let names = |labels: &dyn Fn(u16) -> Option<String>, address| {
+        let symbol = symbols.name(address).map(str::to_string);
+        symbol
+            .or_else(|| labels(address))
+            .or_else(|| symbols.symbolize(address))
+    };
 
+    match command {
+        Command::Run { program_path } => run(&program_path, args.syntax, symbols),
+        Command::Disasm {
+            program_path,
+            origin,
+            recursive,
+            entry,
+        } => {
+            let image = fs::read(&program_path)?;
+            if !recursive {
+                let labels = |address| names(&|_| None, address);
+                for line in disasm::linear_sweep(&image, origin) {
+                    if let Some(name) = symbols.name(line.address) {
+                        println!("{}:", name);
+                    }
+                    println!("{}", disasm::format_line(&line, args.syntax, Some(&labels)));
+                }
+                return Ok(());
+            }
+
+            let entry = resolve(&symbols, entry.as_deref(), origin)?;
+            let analysis = analysis::analyze(&image, origin, entry);
+            let generated = |address| analysis.label(address);
+            let labels = |address| names(&generated, address);
+            for line in disasm::recursive_listing(&image, &analysis) {
+                let symbol = symbols.name(line.address).map(str::to_string);
+                if let Some(label) = symbol.or_else(|| analysis.label(line.address)) {
+                    println!("{}:", label);
+                }
+                println!("{}", disasm::format_line(&line, args.syntax, Some(&labels)));
+            }
+            Ok(())
+        }
+        Command::Cfg {
+            program_path,
+            origin,
+            function,
+            output,
+        } => {
+            let image = fs::read(&program_path)?;
+            let function = resolve(&symbols, function.as_deref(), origin)?;
+            let graph = cfg::build(&image, origin, function);
+            let dot = graph.to_dot(args.syntax, &symbols);
+            match output {
+                Some(path) => fs::write(path, dot)?,
+                None => print!("{}", dot),
+            }
+            Ok(())
+        }
+    }
+}
+
+fn run(
+    program_path: &str,
+    syntax: Syntax,
+    mut symbols: SymbolTable,
+) -> Result<(), Box<dyn std::error::Error>> {
     // Read the program file into a byte vector
     let program_bytes =
-        fs::read(&args.program_path).expect("Could not read program file. Does it exist?");
+        fs::read(program_path).expect("Could not read program file. Does it exist?");
 
     // Initialize the CPU and load the program
     let mut cpu = Cpu::new();
@@ -36,19 +195,79 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         match readline {
             Ok(line) => {
                 _ = rl.add_history_entry(line.as_str());
+                let symbolize = |address| symbols.symbolize(address);
                 match line.trim() {
-                    "s" | "step" => {
-                        cpu.step();
+                    "s" | "step" => match cpu.step() {
+                        Ok(ins) => println!("{}", ins.display(syntax).with_symbols(&symbolize)),
+                        // IP stays put, so stepping again fails the same way
+                        Err(err) => println!(
+                            "Decode error: {}. Use 'skip [n]' to step over bytes or 'ip <expr>' to jump.",
+                            err
+                        ),
+                    },
+                    command if command == "skip" || command.starts_with("skip ") => {
+                        let count = command["skip".len()..].trim();
+                        let count = if count.is_empty() {
+                            Ok(1)
+                        } else {
+                            symbols::parse_number(count)
+                        };
+                        match count {
+                            Ok(count) => {
+                                cpu.regs.ip = cpu.regs.ip.wrapping_add(count);
+                                println!("IP {:04X}", cpu.regs.ip);
+                            }
+                            Err(err) => println!("{}", err),
+                        }
+                    }
+                    command if command.starts_with("ip ") => {
+                        let expr = &command["ip ".len()..];
+                        match symbols.resolve(expr) {
+                            Some(address) => {
+                                cpu.regs.ip = address;
+                                println!("IP {:04X}", cpu.regs.ip);
+                            }
+                            None => println!("Unknown address or symbol '{}'", expr.trim()),
+                        }
                     }
                     "r" | "regs" => {
                         println!("{:#?}", cpu.regs);
+                        match symbols.symbolize(cpu.regs.ip) {
+                            Some(name) => {
+                                println!("CS:IP {:04X}:{:04X} <{}>", cpu.regs.cs, cpu.regs.ip, name)
+                            }
+                            None => println!("CS:IP {:04X}:{:04X}", cpu.regs.cs, cpu.regs.ip),
+                        }
+                    }
+                    command if command.starts_with("sym load ") => {
+                        let path = command["sym load ".len()..].trim();
+                        match SymbolTable::load(path) {
+                            Ok(loaded) => {
+                                println!("Loaded {} symbols from {}", loaded.len(), path);
+                                symbols.extend(loaded);
+                            }
+                            Err(err) => println!("Could not load {}: {}", path, err),
+                        }
+                    }
+                    command if command.starts_with("sym ") => {
+                        let expr = &command["sym ".len()..];
+                        let Some(address) = symbols.resolve(expr) else {
+                            println!("Unknown address or symbol '{}'", expr.trim());
+                            continue;
+                        };
+                        match symbols.symbolize(address) {
+                            Some(name) => println!("{:04X} <{}>", address, name),
+                            None => println!("{:04X}", address),
+                        }
                     }
                     "q" | "quit" => {
                         println!("Exiting.");
                         break;
                     }
                     _ => {
-                        println!("Unknown command. Available: s(tep), r(egs), q(uit)");
+                        println!(
+                            "Unknown command. Available: s(tep), r(egs), skip [n], ip <expr>, sym load <file>, sym <expr>, q(uit)"
+                        );
                     }
                 }
             }